@@ -383,6 +383,10 @@ impl serde::Serializer for &mut Serializer {
         self.serialize_variant(index)?;
         self.serialize_struct(name, len)
     }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
 }
 
 impl SerializeSeq for &mut Serializer {
@@ -0,0 +1,148 @@
+//! This crate contains textual address encoding shared by `power` and `power-derive`.
+//!
+//! Functions work with raw address bytes, `power::Address` wraps them.
+
+#![no_std]
+
+extern crate crc;
+
+use core::fmt;
+use crc::crc32;
+
+/// Error returned by `parse`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AddressError {
+    /// Number of address characters, not counting separators, is neither 20 (public
+    /// address) nor 18 (private address).
+    BadLength(usize),
+    /// Character at the position (in chars, counting separators) is not allowed there.
+    BadCharacter { position: usize, character: char },
+    ChecksumMismatch,
+    /// Group or block number is too large to be encoded.
+    OutOfRange,
+}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AddressError::BadLength(len) => write!(f, "bad address length {}", len),
+            AddressError::BadCharacter {
+                position,
+                character,
+            } => write!(f, "bad address character {:?} at position {}", character, position),
+            AddressError::ChecksumMismatch => f.write_str("address checksum mismatch"),
+            AddressError::OutOfRange => f.write_str("address group or block out of range"),
+        }
+    }
+}
+
+/// Returns checksum of address bytes, textual address ends with its last digits.
+pub fn checksum(bytes: &[u8; 8]) -> u32 {
+    crc32::checksum_ieee(&bytes[..])
+}
+
+/// Returns bytes of public address made of group, block and wallet.
+///
+/// Returns `None` if block doesn't fit in 21 bits or wallet in 24 bits.
+pub fn pack(group: u16, block: u64, wallet: u32) -> Option<[u8; 8]> {
+    if block >= 1 << 21 || wallet >= 1 << 24 {
+        return None;
+    }
+    let group = (group as u64) << 5;
+    let wallet = wallet as u64;
+    Some([
+        ((group >> 16) & 0x1f | 0b10000000) as u8,
+        ((group >> 8) & 0xff) as u8,
+        (group & 0xff | ((block >> 16) & 0x1f)) as u8,
+        ((block >> 8) & 0xff) as u8,
+        (block & 0xff) as u8,
+        ((wallet >> 16) & 0xff) as u8,
+        ((wallet >> 8) & 0xff) as u8,
+        (wallet & 0xff) as u8,
+    ])
+}
+
+/// Returns number made of decimal or hex digits, `chars` are `(position, uppercase char)`.
+fn parse_digits(chars: &[(usize, u8)], radix: u64) -> Result<u64, AddressError> {
+    let mut n: u64 = 0;
+    for &(position, c) in chars {
+        let d = match c {
+            b'0'..=b'9' => (c - b'0') as u64,
+            b'A'..=b'F' if radix == 16 => (c - b'A' + 10) as u64,
+            _ => {
+                return Err(AddressError::BadCharacter {
+                    position,
+                    character: c as char,
+                })
+            }
+        };
+        n = n * radix + d;
+    }
+    Ok(n)
+}
+
+/// Returns bytes of textual address.
+///
+/// Lowercase letters are accepted, spaces, `-` and `_` are ignored.
+pub fn parse(address: &str) -> Result<[u8; 8], AddressError> {
+    // Only the first 20 characters are kept, longer input is rejected by length anyway.
+    let mut chars = [(0usize, 0u8); 20];
+    let mut len = 0;
+    for (position, c) in address.chars().enumerate() {
+        match c {
+            ' ' | '-' | '_' => continue,
+            c if c.is_ascii_alphanumeric() => {
+                if len < chars.len() {
+                    chars[len] = (position, c.to_ascii_uppercase() as u8);
+                }
+                len += 1;
+            }
+            _ => {
+                return Err(AddressError::BadCharacter {
+                    position,
+                    character: c,
+                })
+            }
+        }
+    }
+    match len {
+        20 => {
+            let mut g = [0u64; 2];
+            for i in 0..2 {
+                let (position, c) = chars[i];
+                if !c.is_ascii_uppercase() {
+                    return Err(AddressError::BadCharacter {
+                        position,
+                        character: c as char,
+                    });
+                }
+                g[i] = (c - b'A') as u64;
+            }
+            let gr = parse_digits(&chars[2..4], 10)?;
+            let i = parse_digits(&chars[4..18], 10)?;
+            let c = parse_digits(&chars[18..20], 10)?;
+
+            let group = (g[0] * 26 + g[1]) * 100 + gr;
+            if group > 0xffff {
+                return Err(AddressError::OutOfRange);
+            }
+            let v = pack(group as u16, i >> 24, (i & 0xffffff) as u32)
+                .ok_or(AddressError::OutOfRange)?;
+            if c != checksum(&v) as u64 % 100 {
+                return Err(AddressError::ChecksumMismatch);
+            }
+            Ok(v)
+        }
+        18 => {
+            let a = parse_digits(&chars[0..16], 16)?;
+            let c = parse_digits(&chars[16..18], 16)?;
+            let mut v = a.to_be_bytes();
+            v[0] |= 0b10100000;
+            if c != checksum(&v) as u64 & 0xff {
+                return Err(AddressError::ChecksumMismatch);
+            }
+            Ok(v)
+        }
+        len => Err(AddressError::BadLength(len)),
+    }
+}
@@ -1,3 +1,4 @@
+extern crate power_address;
 extern crate proc_macro;
 extern crate syn;
 #[macro_use]
@@ -5,10 +6,24 @@ extern crate quote;
 
 use proc_macro::TokenStream;
 use quote::Tokens;
-use syn::{FnArg, Ident, Item};
+use syn::{FnArg, Ident, Index, Item, LitStr};
 
+/// Largest number of method arguments supported by `try_get_args`, methods with more
+/// arguments are decoded with `get_args`.
+const MAX_CHECKED_ARGS: usize = 12;
+
+/// Exports method callable by transactions.
+///
+/// Arguments which can't be decoded make the call trap after returning `ArgsError`, so
+/// the transaction fails. With `#[power_method(soft_args)]` the call returns `ArgsError`
+/// without trapping instead.
 #[proc_macro_attribute]
-pub fn power_method(_metadata: TokenStream, input: TokenStream) -> TokenStream {
+pub fn power_method(metadata: TokenStream, input: TokenStream) -> TokenStream {
+    let soft_args = match metadata.to_string().trim() {
+        "" => false,
+        "soft_args" => true,
+        other => panic!("unknown power_method option: {}", other),
+    };
     let item: syn::Item = syn::parse(input).expect("failed to parse input");
 
     let mut output = quote! { #item };
@@ -24,6 +39,7 @@ pub fn power_method(_metadata: TokenStream, input: TokenStream) -> TokenStream {
                     match arg {
                         FnArg::Captured(ref c) => {
                             let ct = &c.ty;
+                            let index = Index::from(index);
                             ps.push(quote!{ args.#index });
                             types.push(quote!{ #ct });
                         }
@@ -31,11 +47,29 @@ pub fn power_method(_metadata: TokenStream, input: TokenStream) -> TokenStream {
                     }
                 }
 
+                let body = if types.len() > MAX_CHECKED_ARGS {
+                    quote!{
+                      let args : (#(#types),* ,) = get_args();
+                      set_return(#fn_name(#(#ps),*));
+                    }
+                } else {
+                    let on_error = if soft_args {
+                        quote!{ set_return(e) }
+                    } else {
+                        quote!{ { set_return(&e); panic!("{}", e) } }
+                    };
+                    quote!{
+                      match try_get_args::<(#(#types),* ,)>() {
+                        Ok(args) => set_return(#fn_name(#(#ps),*)),
+                        Err(e) => #on_error,
+                      }
+                    }
+                };
+
                 output = quote!{
                   #[no_mangle]
                   pub fn #wrapper_name(){
-                    let args : (#(#types),* ,) = get_args();
-                    set_return(#fn_name(#(#ps),*));
+                    #body
                   }
 
                   #output
@@ -56,3 +90,25 @@ pub fn power_method(_metadata: TokenStream, input: TokenStream) -> TokenStream {
     }
     output.into()
 }
+
+/// Expands textual address literal into `Address`, checked at compile time.
+///
+/// # Examples
+///
+/// ```ignore
+/// const ADMIN: Address = address!("AA100000001677722185");
+/// ```
+#[proc_macro]
+pub fn address(input: TokenStream) -> TokenStream {
+    let output = match syn::parse::<LitStr>(input) {
+        Ok(lit) => match power_address::parse(&lit.value()) {
+            Ok(bytes) => quote! { ::power::Address([#(#bytes),*]) },
+            Err(e) => {
+                let msg = format!("invalid address {:?}: {}", lit.value(), e);
+                quote! { compile_error!(#msg) }
+            }
+        },
+        Err(_) => quote! { compile_error!("address! expects a string literal") },
+    };
+    output.into()
+}
@@ -1,6 +1,21 @@
 //! This crate contains extern function declarations for
 //! functions provided by wasm VM.
-
+//!
+//! With `mock` feature enabled the functions are implemented natively in thread-local memory
+//! instead, see `mock` module.
+
+#[cfg(feature = "mock")]
+pub mod mock;
+
+#[cfg(feature = "mock")]
+pub use crate::mock::{
+    debug, emit_tx, flush, get_args_raw, get_args_raw_size, get_balance_raw,
+    get_balance_raw_size, get_entropy, get_entropy_size, get_mean_time, get_tx_raw,
+    get_tx_raw_size, set_return, storage_delete, storage_has_key, storage_next_key,
+    storage_next_key_size, storage_read, storage_reset, storage_value_size, storage_write,
+};
+
+#[cfg(not(feature = "mock"))]
 extern "C" {
     /// Appends string of specified length to VM's debug buffer.
     pub fn debug(len: usize, string: *const u8);
@@ -26,6 +41,48 @@ extern "C" {
     /// Creates new or replaces existing storage entry.
     pub fn storage_write(key_size: usize, key: *const u8, value_size: usize, src: *const u8);
 
+    /// Checks whether storage entry with key of specified length exists.
+    ///
+    /// Unlike storage_value_size, returns true for entries with empty value.
+    pub fn storage_has_key(key_size: usize, key: *const u8) -> bool;
+
+    /// Removes storage entry by key of specified length.
+    ///
+    /// Does nothing if there is no such entry.
+    pub fn storage_delete(key_size: usize, key: *const u8);
+
+    /// Finds the first storage key which starts with specified prefix and is greater than
+    /// provided key (in lexicographic byte order) and returns that key's length.
+    ///
+    /// Pass zero-length key to find the first key with the prefix.
+    ///
+    /// # Returns
+    ///
+    /// * found key's length;
+    /// * 0 if there are no more keys with the prefix.
+    pub fn storage_next_key_size(
+        prefix_size: usize,
+        prefix: *const u8,
+        key_size: usize,
+        key: *const u8,
+    ) -> usize;
+
+    /// Copies the key found by storage_next_key_size to provided destination buffer.
+    ///
+    /// Use storage_next_key_size to obtain required destination buffer's length.
+    ///
+    /// # Returns
+    ///
+    /// * True if the key was successfully copied to destination buffer;
+    /// * False if there are no more keys with the prefix.
+    pub fn storage_next_key(
+        prefix_size: usize,
+        prefix: *const u8,
+        key_size: usize,
+        key: *const u8,
+        dst: *mut u8,
+    ) -> bool;
+
     /// Removes all storage entries.
     pub fn storage_reset();
 
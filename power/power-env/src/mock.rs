@@ -0,0 +1,409 @@
+//! Native implementation of VM functions for running contract code in `cargo test`.
+//!
+//! State is kept per thread, so every test gets its own storage and inputs. Use setters
+//! to provide transaction, arguments and other inputs, and getters to inspect
+//! return value, emitted transactions and debug output.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::ops::Bound;
+use std::ptr;
+use std::slice;
+
+#[derive(Default)]
+struct MockEnv {
+    debug_buffer: Vec<u8>,
+    debug_log: Vec<String>,
+    storage: BTreeMap<Vec<u8>, Vec<u8>>,
+    tx: Vec<u8>,
+    args: Vec<u8>,
+    balance: Vec<u8>,
+    entropy: Vec<u8>,
+    mean_time: u64,
+    ret: Option<Vec<u8>>,
+    emitted: Vec<Vec<u8>>,
+}
+
+thread_local! {
+    static ENV: RefCell<MockEnv> = RefCell::new(MockEnv::default());
+}
+
+fn with_env<R, F: FnOnce(&mut MockEnv) -> R>(f: F) -> R {
+    ENV.with(|env| f(&mut env.borrow_mut()))
+}
+
+unsafe fn to_slice<'a>(len: usize, ptr: *const u8) -> &'a [u8] {
+    if len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(ptr, len)
+    }
+}
+
+unsafe fn copy_to(src: &[u8], dst: *mut u8) {
+    ptr::copy_nonoverlapping(src.as_ptr(), dst, src.len());
+}
+
+/// Resets all mock state: storage, inputs and captured outputs.
+pub fn reset() {
+    with_env(|env| *env = MockEnv::default());
+}
+
+/// Sets raw transaction returned by get_tx_raw.
+pub fn set_tx(tx: Vec<u8>) {
+    with_env(|env| env.tx = tx);
+}
+
+/// Sets raw arguments returned by get_args_raw.
+pub fn set_args(args: Vec<u8>) {
+    with_env(|env| env.args = args);
+}
+
+/// Sets raw balances returned by get_balance_raw.
+pub fn set_balance(balance: Vec<u8>) {
+    with_env(|env| env.balance = balance);
+}
+
+/// Sets entropy returned by get_entropy.
+pub fn set_entropy(entropy: Vec<u8>) {
+    with_env(|env| env.entropy = entropy);
+}
+
+/// Sets block's mean time returned by get_mean_time.
+pub fn set_mean_time(mean_time: u64) {
+    with_env(|env| env.mean_time = mean_time);
+}
+
+/// Creates new or replaces existing storage entry.
+pub fn set_storage_entry(key: Vec<u8>, value: Vec<u8>) {
+    with_env(|env| {
+        env.storage.insert(key, value);
+    });
+}
+
+/// Returns copy of all storage entries.
+pub fn storage() -> BTreeMap<Vec<u8>, Vec<u8>> {
+    with_env(|env| env.storage.clone())
+}
+
+/// Returns value passed to the last set_return call and clears it.
+pub fn take_return() -> Option<Vec<u8>> {
+    with_env(|env| env.ret.take())
+}
+
+/// Returns transactions passed to emit_tx so far and clears them.
+pub fn take_emitted() -> Vec<Vec<u8>> {
+    with_env(|env| env.emitted.split_off(0))
+}
+
+/// Returns flushed debug output lines and clears them.
+pub fn take_debug_log() -> Vec<String> {
+    with_env(|env| env.debug_log.split_off(0))
+}
+
+fn next_key(env: &MockEnv, prefix: &[u8], after: &[u8]) -> Option<Vec<u8>> {
+    let start = if after < prefix {
+        Bound::Included(prefix)
+    } else {
+        Bound::Excluded(after)
+    };
+    env.storage
+        .range::<[u8], _>((start, Bound::Unbounded))
+        .next()
+        .map(|(k, _)| k)
+        .filter(|k| k.starts_with(prefix))
+        .cloned()
+}
+
+/// # Safety
+///
+/// `string` must be valid for reads of the given number of bytes.
+pub unsafe fn debug(len: usize, string: *const u8) {
+    let s = to_slice(len, string);
+    with_env(|env| env.debug_buffer.extend_from_slice(s));
+}
+
+/// # Safety
+///
+/// Has no requirements, it is `unsafe` only to match the VM import.
+pub unsafe fn flush() {
+    with_env(|env| {
+        let line = String::from_utf8_lossy(&env.debug_buffer).into_owned();
+        env.debug_buffer.clear();
+        env.debug_log.push(line);
+    });
+}
+
+/// # Safety
+///
+/// `key` must be valid for reads of the given number of bytes.
+pub unsafe fn storage_value_size(key_size: usize, key: *const u8) -> usize {
+    let k = to_slice(key_size, key);
+    with_env(|env| env.storage.get(k).map(|v| v.len()).unwrap_or(0))
+}
+
+/// # Safety
+///
+/// `key` must be valid for reads of `key_size` bytes and `dst` for writes of `value_size`
+/// bytes.
+pub unsafe fn storage_read(key_size: usize, key: *const u8, value_size: usize, dst: *mut u8) {
+    let k = to_slice(key_size, key);
+    with_env(|env| {
+        if let Some(v) = env.storage.get(k) {
+            copy_to(&v[..value_size.min(v.len())], dst);
+        }
+    });
+}
+
+/// # Safety
+///
+/// `key` and `src` must be valid for reads of the given number of bytes.
+pub unsafe fn storage_write(key_size: usize, key: *const u8, value_size: usize, src: *const u8) {
+    let k = to_slice(key_size, key).to_vec();
+    let v = to_slice(value_size, src).to_vec();
+    with_env(|env| {
+        env.storage.insert(k, v);
+    });
+}
+
+/// # Safety
+///
+/// `key` must be valid for reads of the given number of bytes.
+pub unsafe fn storage_has_key(key_size: usize, key: *const u8) -> bool {
+    let k = to_slice(key_size, key);
+    with_env(|env| env.storage.contains_key(k))
+}
+
+/// # Safety
+///
+/// `key` must be valid for reads of the given number of bytes.
+pub unsafe fn storage_delete(key_size: usize, key: *const u8) {
+    let k = to_slice(key_size, key);
+    with_env(|env| {
+        env.storage.remove(k);
+    });
+}
+
+/// # Safety
+///
+/// `prefix` and `key` must be valid for reads of the given number of bytes.
+pub unsafe fn storage_next_key_size(
+    prefix_size: usize,
+    prefix: *const u8,
+    key_size: usize,
+    key: *const u8,
+) -> usize {
+    let p = to_slice(prefix_size, prefix);
+    let k = to_slice(key_size, key);
+    with_env(|env| next_key(env, p, k).map(|k| k.len()).unwrap_or(0))
+}
+
+/// # Safety
+///
+/// `prefix` and `key` must be valid for reads of the given number of bytes, and `dst` for
+/// writes of the length returned by `storage_next_key_size`.
+pub unsafe fn storage_next_key(
+    prefix_size: usize,
+    prefix: *const u8,
+    key_size: usize,
+    key: *const u8,
+    dst: *mut u8,
+) -> bool {
+    let p = to_slice(prefix_size, prefix);
+    let k = to_slice(key_size, key);
+    match with_env(|env| next_key(env, p, k)) {
+        Some(next) => {
+            copy_to(&next[..], dst);
+            true
+        }
+        None => false,
+    }
+}
+
+/// # Safety
+///
+/// Has no requirements, it is `unsafe` only to match the VM import.
+pub unsafe fn storage_reset() {
+    with_env(|env| env.storage.clear());
+}
+
+/// # Safety
+///
+/// Has no requirements, it is `unsafe` only to match the VM import.
+pub unsafe fn get_tx_raw_size() -> usize {
+    with_env(|env| env.tx.len())
+}
+
+/// # Safety
+///
+/// `dst` must be valid for writes of `get_tx_raw_size()` bytes.
+pub unsafe fn get_tx_raw(dst: *mut u8) -> bool {
+    with_env(|env| {
+        copy_to(&env.tx[..], dst);
+        !env.tx.is_empty()
+    })
+}
+
+/// # Safety
+///
+/// Has no requirements, it is `unsafe` only to match the VM import.
+pub unsafe fn get_args_raw_size() -> usize {
+    with_env(|env| env.args.len())
+}
+
+/// # Safety
+///
+/// `dst` must be valid for writes of `get_args_raw_size()` bytes.
+pub unsafe fn get_args_raw(dst: *mut u8) -> bool {
+    with_env(|env| {
+        copy_to(&env.args[..], dst);
+        true
+    })
+}
+
+/// # Safety
+///
+/// Has no requirements, it is `unsafe` only to match the VM import.
+pub unsafe fn get_balance_raw_size() -> usize {
+    with_env(|env| env.balance.len())
+}
+
+/// # Safety
+///
+/// `dst` must be valid for writes of `get_balance_raw_size()` bytes.
+pub unsafe fn get_balance_raw(dst: *mut u8) -> bool {
+    with_env(|env| {
+        copy_to(&env.balance[..], dst);
+        true
+    })
+}
+
+/// # Safety
+///
+/// Has no requirements, it is `unsafe` only to match the VM import.
+pub unsafe fn get_entropy_size() -> usize {
+    with_env(|env| env.entropy.len())
+}
+
+/// # Safety
+///
+/// `dst` must be valid for writes of `get_entropy_size()` bytes.
+pub unsafe fn get_entropy(dst: *mut u8) -> bool {
+    with_env(|env| {
+        copy_to(&env.entropy[..], dst);
+        true
+    })
+}
+
+/// # Safety
+///
+/// Has no requirements, it is `unsafe` only to match the VM import.
+pub unsafe fn get_mean_time() -> u64 {
+    with_env(|env| env.mean_time)
+}
+
+/// # Safety
+///
+/// `ret` must be valid for reads of the given number of bytes.
+pub unsafe fn set_return(len: usize, ret: *const u8) {
+    let r = to_slice(len, ret).to_vec();
+    with_env(|env| env.ret = Some(r));
+}
+
+/// # Safety
+///
+/// `tx` must be valid for reads of the given number of bytes.
+pub unsafe fn emit_tx(len: usize, tx: *const u8) {
+    let t = to_slice(len, tx).to_vec();
+    with_env(|env| env.emitted.push(t));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn write(key: &[u8], value: &[u8]) {
+        unsafe { storage_write(key.len(), key.as_ptr(), value.len(), value.as_ptr()) }
+    }
+
+    fn read(key: &[u8]) -> Vec<u8> {
+        unsafe {
+            let mut v = vec![0u8; storage_value_size(key.len(), key.as_ptr())];
+            storage_read(key.len(), key.as_ptr(), v.len(), v.as_mut_ptr());
+            v
+        }
+    }
+
+    fn next(prefix: &[u8], key: &[u8]) -> Option<Vec<u8>> {
+        unsafe {
+            let (p, k) = (prefix, key);
+            let size = storage_next_key_size(p.len(), p.as_ptr(), k.len(), k.as_ptr());
+            let mut next = vec![0u8; size];
+            if storage_next_key(p.len(), p.as_ptr(), k.len(), k.as_ptr(), next.as_mut_ptr()) {
+                Some(next)
+            } else {
+                None
+            }
+        }
+    }
+
+    #[test]
+    fn storage_test() {
+        reset();
+        write(b"a1", b"x");
+        write(b"a2", b"yy");
+        write(b"b1", b"z");
+        assert_eq!(read(b"a2"), b"yy");
+        assert!(unsafe { storage_has_key(2, b"a1".as_ptr()) });
+
+        assert_eq!(next(b"a", b""), Some(b"a1".to_vec()));
+        assert_eq!(next(b"a", b"a1"), Some(b"a2".to_vec()));
+        assert_eq!(next(b"a", b"a2"), None);
+
+        // Empty value is kept as an entry, like the host does.
+        write(b"a1", b"");
+        assert!(unsafe { storage_has_key(2, b"a1".as_ptr()) });
+        assert_eq!(read(b"a1"), b"");
+        assert_eq!(next(b"a", b""), Some(b"a1".to_vec()));
+
+        unsafe { storage_delete(2, b"a1".as_ptr()) };
+        unsafe { storage_delete(2, b"a2".as_ptr()) };
+        assert!(!unsafe { storage_has_key(2, b"a1".as_ptr()) });
+        assert_eq!(next(b"a", b""), None);
+        assert_eq!(storage().len(), 1);
+
+        unsafe { storage_reset() };
+        assert!(storage().is_empty());
+    }
+
+    #[test]
+    fn inputs_test() {
+        reset();
+        set_args(vec![1, 2, 3]);
+        set_mean_time(42);
+        unsafe {
+            let mut args = vec![0u8; get_args_raw_size()];
+            get_args_raw(args.as_mut_ptr());
+            assert_eq!(args, [1, 2, 3]);
+            assert_eq!(get_tx_raw_size(), 0);
+            assert!(!get_tx_raw(args.as_mut_ptr()));
+            assert_eq!(get_mean_time(), 42);
+        }
+    }
+
+    #[test]
+    fn outputs_test() {
+        reset();
+        unsafe {
+            set_return(2, [7, 8].as_ptr());
+            emit_tx(1, [9].as_ptr());
+            debug(5, b"hello".as_ptr());
+            flush();
+        }
+        assert_eq!(take_return(), Some(vec![7, 8]));
+        assert_eq!(take_return(), None);
+        assert_eq!(take_emitted(), vec![vec![9]]);
+        assert!(take_emitted().is_empty());
+        assert_eq!(take_debug_log(), vec!["hello".to_string()]);
+    }
+}
@@ -0,0 +1,190 @@
+use crate::*;
+
+use serde::de::{Deserializer, IgnoredAny, SeqAccess, Visitor};
+use serde::ser::{SerializeMap, Serializer};
+
+/// Error returned by `try_get_args`.
+///
+/// Serializes to a msgpack map with `"error": "bad_args"`, so it can be passed to
+/// `set_return` to report the problem back to the caller.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArgsError {
+    /// Arguments are not a msgpack array.
+    Malformed,
+    /// Number of arguments doesn't match.
+    Count { expected: usize, got: usize },
+    /// Argument at position can't be decoded as expected type.
+    Type {
+        position: usize,
+        expected: &'static str,
+    },
+}
+
+impl Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArgsError::Malformed => f.write_str("arguments are not an array"),
+            ArgsError::Count { expected, got } => {
+                write!(f, "expected {} arguments, got {}", expected, got)
+            }
+            ArgsError::Type { position, expected } => {
+                write!(f, "argument {} is not {}", position, expected)
+            }
+        }
+    }
+}
+
+impl Serialize for ArgsError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let len = match self {
+            ArgsError::Malformed => 3,
+            _ => 5,
+        };
+        let mut map = serializer.serialize_map(Some(len))?;
+        map.serialize_entry("error", "bad_args")?;
+        match self {
+            ArgsError::Malformed => {
+                map.serialize_entry("reason", "malformed")?;
+            }
+            ArgsError::Count { expected, got } => {
+                map.serialize_entry("reason", "count")?;
+                map.serialize_entry("expected", expected)?;
+                map.serialize_entry("got", got)?;
+            }
+            ArgsError::Type { position, expected } => {
+                map.serialize_entry("reason", "type")?;
+                map.serialize_entry("position", position)?;
+                map.serialize_entry("expected", expected)?;
+            }
+        }
+        map.serialize_entry("message", &format!("{}", self))?;
+        map.end()
+    }
+}
+
+/// Decodes next argument of `expected` ones, `position` is its index.
+fn next_arg<'de, A, T>(seq: &mut A, position: usize, expected: usize) -> Result<T, ArgsError>
+where
+    A: SeqAccess<'de>,
+    T: DeserializeOwned,
+{
+    match seq.next_element::<T>() {
+        Ok(Some(x)) => Ok(x),
+        Ok(None) => Err(ArgsError::Count {
+            expected: expected,
+            got: position,
+        }),
+        Err(_) => Err(ArgsError::Type {
+            position: position,
+            expected: core::any::type_name::<T>(),
+        }),
+    }
+}
+
+/// Checks number of arguments before decoding them, if the array tells it.
+fn check_count<'de, A: SeqAccess<'de>>(seq: &A, expected: usize) -> Result<(), ArgsError> {
+    match seq.size_hint() {
+        Some(got) if got != expected => Err(ArgsError::Count {
+            expected: expected,
+            got: got,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Checks that there are no arguments left after `expected` ones.
+fn check_end<'de, A: SeqAccess<'de>>(seq: &mut A, expected: usize) -> Result<(), ArgsError> {
+    let mut got = expected;
+    loop {
+        match seq.next_element::<IgnoredAny>() {
+            Ok(Some(_)) => got += 1,
+            Ok(None) if got == expected => return Ok(()),
+            Ok(None) => {
+                return Err(ArgsError::Count {
+                    expected: expected,
+                    got: got,
+                })
+            }
+            Err(_) => return Err(ArgsError::Malformed),
+        }
+    }
+}
+
+/// Method arguments decodable one by one from msgpack array, see `try_get_args`.
+pub trait Args: Sized {
+    /// Decodes arguments from elements of the array.
+    fn from_seq<'de, A: SeqAccess<'de>>(seq: A) -> Result<Self, ArgsError>;
+
+    /// Decodes arguments from msgpack array.
+    fn from_bytes(bytes: &[u8]) -> Result<Self, ArgsError> {
+        match deserialize::<DecodedArgs<Self>>(bytes) {
+            Some(DecodedArgs(res)) => res,
+            None => Err(ArgsError::Malformed),
+        }
+    }
+}
+
+/// Result of decoding arguments, the array itself is decoded even if an argument fails.
+struct DecodedArgs<T>(Result<T, ArgsError>);
+
+impl<'de, T: Args> Deserialize<'de> for DecodedArgs<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(ArgsVisitor(core::marker::PhantomData))
+    }
+}
+
+struct ArgsVisitor<T>(core::marker::PhantomData<T>);
+
+impl<'de, T: Args> Visitor<'de> for ArgsVisitor<T> {
+    type Value = DecodedArgs<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("array of arguments")
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        Ok(DecodedArgs(T::from_seq(seq)))
+    }
+}
+
+impl Args for () {
+    fn from_seq<'de, A: SeqAccess<'de>>(mut seq: A) -> Result<Self, ArgsError> {
+        check_count(&seq, 0)?;
+        check_end(&mut seq, 0)
+    }
+}
+
+macro_rules! impl_args_for_tuple {
+    ($len:expr => $($name:ident $index:tt)+) => {
+        impl<$($name: DeserializeOwned),+> Args for ($($name,)+) {
+            fn from_seq<'de, A: SeqAccess<'de>>(mut seq: A) -> Result<Self, ArgsError> {
+                check_count(&seq, $len)?;
+                let args = ($(next_arg::<A, $name>(&mut seq, $index, $len)?,)+);
+                check_end(&mut seq, $len)?;
+                Ok(args)
+            }
+        }
+    };
+}
+
+impl_args_for_tuple!(1 => T0 0);
+impl_args_for_tuple!(2 => T0 0 T1 1);
+impl_args_for_tuple!(3 => T0 0 T1 1 T2 2);
+impl_args_for_tuple!(4 => T0 0 T1 1 T2 2 T3 3);
+impl_args_for_tuple!(5 => T0 0 T1 1 T2 2 T3 3 T4 4);
+impl_args_for_tuple!(6 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5);
+impl_args_for_tuple!(7 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6);
+impl_args_for_tuple!(8 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7);
+impl_args_for_tuple!(9 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7 T8 8);
+impl_args_for_tuple!(10 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7 T8 8 T9 9);
+impl_args_for_tuple!(11 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7 T8 8 T9 9 T10 10);
+impl_args_for_tuple!(12 => T0 0 T1 1 T2 2 T3 3 T4 4 T5 5 T6 6 T7 7 T8 8 T9 9 T10 10 T11 11);
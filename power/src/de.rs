@@ -1,5 +1,7 @@
 use crate::*;
 
+use core::convert::TryFrom;
+
 use corepack::from_bytes;
 pub use serde::de::DeserializeOwned;
 use serde::de::{self, Deserializer, MapAccess, SeqAccess, Visitor};
@@ -18,13 +20,23 @@ impl<'de> Visitor<'de> for AddressVisitor {
     type Value = Address;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str("binary of len 8")
+        formatter.write_str("binary of len 8 or textual address")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Address::parse(value).map_err(E::custom)
     }
 
     fn visit_bytes<E>(self, value: &[u8]) -> Result<Self::Value, E>
     where
         E: de::Error,
     {
+        if value.len() != 8 {
+            return Err(E::invalid_length(value.len(), &self));
+        }
         Ok(Self::Value::from_slice(value))
     }
 
@@ -32,11 +44,19 @@ impl<'de> Visitor<'de> for AddressVisitor {
     where
         A: SeqAccess<'de>,
     {
-        let mut v: Vec<u8> = Vec::with_capacity(8);
+        // Elements out of u8 range are rejected by u8 deserialization.
+        let mut v = [0u8; 8];
+        let mut len = 0;
         while let Some(value) = seq.next_element()? {
-            v.push(value);
+            if len < 8 {
+                v[len] = value;
+            }
+            len += 1;
         }
-        Ok(Self::Value::from_slice(&v[..]))
+        if len != 8 {
+            return Err(de::Error::invalid_length(len, &self));
+        }
+        Ok(Address(v))
     }
 }
 
@@ -49,6 +69,39 @@ impl<'de> Deserialize<'de> for Address {
     }
 }
 
+struct CurrencyVisitor;
+
+impl<'de> Visitor<'de> for CurrencyVisitor {
+    type Value = Currency;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("currency ticker as binary or string")
+    }
+
+    fn visit_bytes<E>(self, value: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Currency::from_bytes(value))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Currency::from_bytes(value.as_bytes()))
+    }
+}
+
+impl<'de> Deserialize<'de> for Currency {
+    fn deserialize<D>(deserializer: D) -> Result<Currency, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(CurrencyVisitor)
+    }
+}
+
 struct ValueVisitor;
 
 impl<'de> Visitor<'de> for ValueVisitor {
@@ -78,6 +131,13 @@ impl<'de> Visitor<'de> for ValueVisitor {
         Ok(s.into())
     }
 
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Boolean(v))
+    }
+
     fn visit_none<E>(self) -> Result<Self::Value, E> {
         Ok(Value::Nil)
     }
@@ -117,3 +177,58 @@ impl<'de> Deserialize<'de> for Value {
         deserializer.deserialize_any(ValueVisitor)
     }
 }
+
+/// Decodes `Value` first and then converts it with `TryFrom`.
+fn deserialize_via_value<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: for<'a> TryFrom<&'a Value, Error = TxDecodeError>,
+{
+    let v = Value::deserialize(deserializer)?;
+    T::try_from(&v).map_err(de::Error::custom)
+}
+
+impl<'de> Deserialize<'de> for TxKind {
+    fn deserialize<D>(deserializer: D) -> Result<TxKind, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_via_value(deserializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Purpose {
+    fn deserialize<D>(deserializer: D) -> Result<Purpose, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_via_value(deserializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D>(deserializer: D) -> Result<Amount, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_via_value(deserializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PayloadItem {
+    fn deserialize<D>(deserializer: D) -> Result<PayloadItem, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_via_value(deserializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Tx {
+    fn deserialize<D>(deserializer: D) -> Result<Tx, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_via_value(deserializer)
+    }
+}
@@ -0,0 +1,348 @@
+use crate::*;
+
+/// Maximum supported number of decimal places, 10^19 still fits in `u64`.
+pub const MAX_DECIMALS: u8 = 19;
+
+/// Number of decimal places for currencies missing in `DecimalsRegistry`.
+pub const DEFAULT_DECIMALS: u8 = 9;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DecimalError {
+    /// Text is not a decimal number or amount.
+    Invalid,
+    /// Result doesn't fit in `u64` units.
+    Overflow,
+    /// More decimal places than supported or than allowed by currency.
+    Precision,
+    DivisionByZero,
+}
+
+impl Display for DecimalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecimalError::Invalid => f.write_str("invalid decimal number"),
+            DecimalError::Overflow => f.write_str("decimal overflow"),
+            DecimalError::Precision => f.write_str("too many decimal places"),
+            DecimalError::DivisionByZero => f.write_str("division by zero"),
+        }
+    }
+}
+
+/// How to round a result which can't be represented exactly.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rounding {
+    /// Towards zero.
+    Down,
+    /// Away from zero.
+    Up,
+    /// To nearest, ties away from zero.
+    HalfUp,
+    /// To nearest, ties to even.
+    HalfEven,
+}
+
+fn pow10(decimals: u8) -> u128 {
+    10u128.pow(decimals as u32)
+}
+
+/// Returns `n / d` rounded according to mode.
+fn div_round(n: u128, d: u128, rounding: Rounding) -> u128 {
+    let q = n / d;
+    let r = n % d;
+    if r == 0 {
+        return q;
+    }
+    let up = match rounding {
+        Rounding::Down => false,
+        Rounding::Up => true,
+        Rounding::HalfUp => r * 2 >= d,
+        Rounding::HalfEven => r * 2 > d || (r * 2 == d && q % 2 == 1),
+    };
+    if up {
+        q + 1
+    } else {
+        q
+    }
+}
+
+fn to_u64(x: u128) -> Result<u64, DecimalError> {
+    if x > core::u64::MAX as u128 {
+        Err(DecimalError::Overflow)
+    } else {
+        Ok(x as u64)
+    }
+}
+
+/// Non-negative fixed-point number: `units / 10^decimals`.
+///
+/// # Examples
+///
+/// ```
+/// # use power::*;
+/// let a = Decimal::parse("12.345", 9).unwrap();
+/// assert_eq!(12_345_000_000, a.units());
+/// assert_eq!("12.345", a.to_string());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Decimal {
+    units: u64,
+    decimals: u8,
+}
+
+impl Decimal {
+    pub fn new(units: u64, decimals: u8) -> Result<Decimal, DecimalError> {
+        if decimals > MAX_DECIMALS {
+            return Err(DecimalError::Precision);
+        }
+        Ok(Decimal {
+            units: units,
+            decimals: decimals,
+        })
+    }
+
+    /// Returns value in smallest units, e.g. as stored in `Amount::amount`.
+    pub fn units(&self) -> u64 {
+        self.units
+    }
+
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.units == 0
+    }
+
+    /// Parses text like `12.345` into a number with specified decimal places.
+    ///
+    /// Fails with `DecimalError::Precision` if text has more decimal places.
+    pub fn parse(s: &str, decimals: u8) -> Result<Decimal, DecimalError> {
+        if decimals > MAX_DECIMALS {
+            return Err(DecimalError::Precision);
+        }
+        let (int, frac) = match s.find('.') {
+            Some(i) => (&s[..i], &s[i + 1..]),
+            None => (s, ""),
+        };
+        let digits = |p: &str| p.bytes().all(|b| b.is_ascii_digit());
+        if int.len() == 0 || !digits(int) || !digits(frac) || s.ends_with('.') {
+            return Err(DecimalError::Invalid);
+        }
+        let frac = frac.trim_end_matches('0');
+        if frac.len() > decimals as usize {
+            return Err(DecimalError::Precision);
+        }
+        let mut units: u128 = 0;
+        for b in int.bytes().chain(frac.bytes()) {
+            units = units * 10 + (b - b'0') as u128;
+            if units > core::u64::MAX as u128 {
+                return Err(DecimalError::Overflow);
+            }
+        }
+        units *= pow10(decimals - frac.len() as u8);
+        Decimal::new(to_u64(units)?, decimals)
+    }
+
+    /// Converts to another number of decimal places, rounding if precision is lost.
+    pub fn rescale(&self, decimals: u8, rounding: Rounding) -> Result<Decimal, DecimalError> {
+        if decimals > MAX_DECIMALS {
+            return Err(DecimalError::Precision);
+        }
+        let units = if decimals >= self.decimals {
+            self.units as u128 * pow10(decimals - self.decimals)
+        } else {
+            div_round(self.units as u128, pow10(self.decimals - decimals), rounding)
+        };
+        Decimal::new(to_u64(units)?, decimals)
+    }
+
+    /// Returns both numbers converted to the larger number of decimal places.
+    fn align(&self, other: &Decimal) -> Result<(u64, u64, u8), DecimalError> {
+        let d = self.decimals.max(other.decimals);
+        let a = self.rescale(d, Rounding::Down)?;
+        let b = other.rescale(d, Rounding::Down)?;
+        Ok((a.units, b.units, d))
+    }
+
+    /// Returns sum with the larger number of decimal places of the two.
+    pub fn checked_add(&self, other: &Decimal) -> Result<Decimal, DecimalError> {
+        let (a, b, d) = self.align(other)?;
+        Decimal::new(a.checked_add(b).ok_or(DecimalError::Overflow)?, d)
+    }
+
+    /// Returns difference with the larger number of decimal places of the two.
+    ///
+    /// Fails with `DecimalError::Overflow` if result is negative.
+    pub fn checked_sub(&self, other: &Decimal) -> Result<Decimal, DecimalError> {
+        let (a, b, d) = self.align(other)?;
+        Decimal::new(a.checked_sub(b).ok_or(DecimalError::Overflow)?, d)
+    }
+
+    /// Returns product with decimal places of `self`.
+    pub fn checked_mul(
+        &self,
+        other: &Decimal,
+        rounding: Rounding,
+    ) -> Result<Decimal, DecimalError> {
+        let n = self.units as u128 * other.units as u128;
+        let units = div_round(n, pow10(other.decimals), rounding);
+        Decimal::new(to_u64(units)?, self.decimals)
+    }
+
+    /// Returns quotient with decimal places of `self`.
+    pub fn checked_div(
+        &self,
+        other: &Decimal,
+        rounding: Rounding,
+    ) -> Result<Decimal, DecimalError> {
+        if other.units == 0 {
+            return Err(DecimalError::DivisionByZero);
+        }
+        let n = (self.units as u128)
+            .checked_mul(pow10(other.decimals))
+            .ok_or(DecimalError::Overflow)?;
+        let units = div_round(n, other.units as u128, rounding);
+        Decimal::new(to_u64(units)?, self.decimals)
+    }
+}
+
+impl Display for Decimal {
+    /// Formats number without trailing zeros in fractional part.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let p = pow10(self.decimals) as u64;
+        let int = self.units / p;
+        let frac = self.units % p;
+        if frac == 0 {
+            return write!(f, "{}", int);
+        }
+        let frac = format!("{:0width$}", frac, width = self.decimals as usize);
+        write!(f, "{}.{}", int, frac.trim_end_matches('0'))
+    }
+}
+
+/// Number of decimal places of every currency.
+///
+/// # Examples
+///
+/// ```
+/// # use power::*;
+/// let registry = DecimalsRegistry::new(DEFAULT_DECIMALS).with("FTT".parse().unwrap(), 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct DecimalsRegistry {
+    default: u8,
+    decimals: BTreeMap<Currency, u8>,
+}
+
+impl DecimalsRegistry {
+    /// Creates registry using `default` for currencies not registered explicitly.
+    pub fn new(default: u8) -> Self {
+        DecimalsRegistry {
+            default: default.min(MAX_DECIMALS),
+            decimals: BTreeMap::new(),
+        }
+    }
+
+    pub fn with(mut self, currency: Currency, decimals: u8) -> Self {
+        self.set(currency, decimals);
+        self
+    }
+
+    pub fn set(&mut self, currency: Currency, decimals: u8) {
+        self.decimals.insert(currency, decimals.min(MAX_DECIMALS));
+    }
+
+    pub fn decimals(&self, currency: &Currency) -> u8 {
+        self.decimals.get(currency).cloned().unwrap_or(self.default)
+    }
+}
+
+impl Default for DecimalsRegistry {
+    fn default() -> Self {
+        DecimalsRegistry::new(DEFAULT_DECIMALS)
+    }
+}
+
+/// Amount of currency with decimal places taken from `DecimalsRegistry`.
+///
+/// # Examples
+///
+/// ```
+/// # use power::*;
+/// let registry = DecimalsRegistry::default();
+/// let a = TokenAmount::parse("12.345 SK", &registry).unwrap();
+/// assert_eq!("12.345 SK", a.to_string());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct TokenAmount {
+    pub currency: Currency,
+    pub value: Decimal,
+}
+
+impl TokenAmount {
+    /// Parses text like `12.345 SK`.
+    pub fn parse(s: &str, registry: &DecimalsRegistry) -> Result<TokenAmount, DecimalError> {
+        let mut parts = s.split_whitespace();
+        let (value, currency) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(v), Some(c), None) => (v, c),
+            _ => return Err(DecimalError::Invalid),
+        };
+        let currency: Currency = currency.parse().map_err(|_| DecimalError::Invalid)?;
+        let value = Decimal::parse(value, registry.decimals(&currency))?;
+        Ok(TokenAmount {
+            currency: currency,
+            value: value,
+        })
+    }
+
+    /// Converts to `Amount` in smallest units of the currency.
+    ///
+    /// Fails with `DecimalError::Precision` if value has more decimal places than the
+    /// currency allows.
+    pub fn to_amount(&self, registry: &DecimalsRegistry) -> Result<Amount, DecimalError> {
+        let d = registry.decimals(&self.currency);
+        let v = self.value.rescale(d, Rounding::Down)?;
+        if v.rescale(self.value.decimals, Rounding::Down)? != self.value {
+            return Err(DecimalError::Precision);
+        }
+        Ok(Amount {
+            currency: self.currency.clone(),
+            amount: v.units,
+        })
+    }
+}
+
+impl Display for TokenAmount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.value, self.currency)
+    }
+}
+
+impl Amount {
+    pub fn to_token(&self, registry: &DecimalsRegistry) -> TokenAmount {
+        TokenAmount {
+            currency: self.currency.clone(),
+            value: Decimal {
+                units: self.amount,
+                decimals: registry.decimals(&self.currency),
+            },
+        }
+    }
+}
+
+impl PayloadItem {
+    pub fn from_token(
+        p: Purpose,
+        amount: &TokenAmount,
+        registry: &DecimalsRegistry,
+    ) -> Result<PayloadItem, DecimalError> {
+        Ok(PayloadItem {
+            purpose: p,
+            amount: amount.to_amount(registry)?,
+        })
+    }
+
+    pub fn to_token(&self, registry: &DecimalsRegistry) -> TokenAmount {
+        self.amount.to_token(registry)
+    }
+}
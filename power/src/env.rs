@@ -1,5 +1,7 @@
 use crate::*;
 
+use core::convert::TryFrom;
+
 use power_env;
 
 pub fn debug(string: &str) {
@@ -21,10 +23,7 @@ where
 {
     let k = serialize(key);
     let v = serialize(value);
-
-    unsafe {
-        power_env::storage_write(k.len(), k.as_ptr(), v.len(), v.as_ptr());
-    }
+    write_raw(&k[..], &v[..]);
 }
 
 pub fn read<T1, T2>(key: &T1) -> Option<T2>
@@ -33,40 +32,397 @@ where
     T2: DeserializeOwned,
 {
     let k = serialize(key);
+    let v = read_raw(&k[..])?;
+    deserialize(&v[..])
+}
 
-    unsafe {
-        let value_size = power_env::storage_value_size(k.len(), k.as_ptr());
-        if value_size == 0 {
-            return None;
+/// Error returned by `try_read`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StorageError {
+    /// Entry exists, but its value is empty.
+    EmptyValue,
+    /// Entry exists, but its value can't be decoded as requested type.
+    Decode,
+}
+
+impl Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StorageError::EmptyValue => f.write_str("storage value is empty"),
+            StorageError::Decode => f.write_str("storage value can't be decoded"),
         }
-        let mut v: Vec<u8> = Vec::with_capacity(value_size);
-        power_env::storage_read(k.len(), k.as_ptr(), value_size, v.as_mut_ptr());
-        v.set_len(value_size);
+    }
+}
 
-        return deserialize(&v[..]);
+/// Reads value like `read`, but tells a missing entry apart from a broken one.
+///
+/// # Returns
+///
+/// * `Ok(Some(value))` for existing entry;
+/// * `Ok(None)` for non-existent entry;
+/// * `Err(StorageError)` if the entry exists but can't be read as `T2`.
+///
+/// # Examples
+///
+/// ```
+/// # use power::*;
+/// # #[cfg(feature = "mock")] {
+/// write("count", &1u64);
+/// assert_eq!(Ok(Some(1u64)), try_read("count"));
+/// assert_eq!(Err(StorageError::Decode), try_read::<_, String>("count"));
+///
+/// delete("count");
+/// assert_eq!(Ok(None), try_read::<_, u64>("count"));
+///
+/// power::mock::set_storage_entry(serialize("count"), vec![]);
+/// assert_eq!(Err(StorageError::EmptyValue), try_read::<_, u64>("count"));
+/// # }
+/// ```
+pub fn try_read<T1, T2>(key: &T1) -> Result<Option<T2>, StorageError>
+where
+    T1: ?Sized + Serialize,
+    T2: DeserializeOwned,
+{
+    let k = serialize(key);
+    if !has_raw_key(&k[..]) {
+        return Ok(None);
+    }
+    match read_raw(&k[..]) {
+        Some(v) => match deserialize(&v[..]) {
+            Some(x) => Ok(Some(x)),
+            None => Err(StorageError::Decode),
+        },
+        None => Err(StorageError::EmptyValue),
     }
 }
 
+/// Removes the entry, does nothing if there is no such entry.
 pub fn delete<T>(key: &T)
 where
     T: ?Sized + Serialize,
 {
     let k = serialize(key);
+    delete_raw(&k[..]);
+}
+
+pub(crate) fn write_raw(key: &[u8], value: &[u8]) {
+    unsafe {
+        power_env::storage_write(key.len(), key.as_ptr(), value.len(), value.as_ptr());
+    }
+}
 
+pub(crate) fn delete_raw(key: &[u8]) {
     unsafe {
-        power_env::storage_write(k.len(), k.as_ptr(), 0, 0 as *const u8);
+        power_env::storage_delete(key.len(), key.as_ptr());
     }
 }
 
+/// Returns true if the entry exists, even with empty value.
 pub fn has_key<T>(key: &T) -> bool
 where
     T: ?Sized + Serialize,
 {
     let k = serialize(key);
+    has_raw_key(&k[..])
+}
+
+fn has_raw_key(key: &[u8]) -> bool {
+    unsafe {
+        return power_env::storage_has_key(key.len(), key.as_ptr());
+    }
+}
+
+fn next_key_raw(prefix: &[u8], after: &[u8]) -> Option<Vec<u8>> {
+    unsafe {
+        let size =
+            power_env::storage_next_key_size(prefix.len(), prefix.as_ptr(), after.len(), after.as_ptr());
+        if size == 0 {
+            return None;
+        }
+        let mut dst: Vec<u8> = Vec::with_capacity(size);
+        if !power_env::storage_next_key(
+            prefix.len(),
+            prefix.as_ptr(),
+            after.len(),
+            after.as_ptr(),
+            dst.as_mut_ptr(),
+        ) {
+            return None;
+        }
+        dst.set_len(size);
+        return Some(dst);
+    }
+}
+
+fn value_size_raw(key: &[u8]) -> usize {
+    unsafe { power_env::storage_value_size(key.len(), key.as_ptr()) }
+}
 
+pub(crate) fn read_raw(key: &[u8]) -> Option<Vec<u8>> {
     unsafe {
-        let value_size = power_env::storage_value_size(k.len(), k.as_ptr());
-        return value_size > 0;
+        let value_size = value_size_raw(key);
+        if value_size == 0 {
+            return None;
+        }
+        let mut v: Vec<u8> = Vec::with_capacity(value_size);
+        power_env::storage_read(key.len(), key.as_ptr(), value_size, v.as_mut_ptr());
+        v.set_len(value_size);
+        return Some(v);
+    }
+}
+
+/// Iterator over raw storage keys starting with specified prefix, see `raw_keys_with_prefix`.
+pub struct RawKeys {
+    prefix: Vec<u8>,
+    last: Vec<u8>,
+    done: bool,
+}
+
+impl Iterator for RawKeys {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        if self.done {
+            return None;
+        }
+        match next_key_raw(&self.prefix[..], &self.last[..]) {
+            Some(k) => {
+                self.last = k.clone();
+                Some(k)
+            }
+            None => {
+                self.done = true;
+                None
+            }
+        }
+    }
+}
+
+/// Returns iterator over encoded storage keys starting with specified byte prefix.
+///
+/// Keys are yielded in lexicographic byte order.
+pub fn raw_keys_with_prefix(prefix: &[u8]) -> RawKeys {
+    RawKeys {
+        prefix: prefix.to_vec(),
+        last: Vec::new(),
+        done: false,
+    }
+}
+
+/// Iterator over decoded storage keys, see `keys_with_prefix`.
+pub struct Keys<K> {
+    raw: RawKeys,
+    _marker: core::marker::PhantomData<K>,
+}
+
+impl<K: DeserializeOwned> Iterator for Keys<K> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        while let Some(k) = self.raw.next() {
+            if value_size_raw(&k[..]) == 0 {
+                continue;
+            }
+            if let Some(key) = deserialize(&k[..]) {
+                return Some(key);
+            }
+        }
+        None
+    }
+}
+
+/// Returns iterator over storage keys whose encoded form starts with specified byte prefix.
+///
+/// Keys which can't be decoded as `K` are skipped, so are keys of entries with empty value,
+/// like in `iter_prefix`.
+pub fn keys_with_prefix<K: DeserializeOwned>(prefix: &[u8]) -> Keys<K> {
+    Keys {
+        raw: raw_keys_with_prefix(prefix),
+        _marker: core::marker::PhantomData,
+    }
+}
+
+/// Iterator over decoded storage entries, see `iter_prefix`.
+pub struct PrefixIter<K, V> {
+    raw: RawKeys,
+    _marker: core::marker::PhantomData<(K, V)>,
+}
+
+impl<K: DeserializeOwned, V: DeserializeOwned> Iterator for PrefixIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        while let Some(k) = self.raw.next() {
+            let v = match read_raw(&k[..]) {
+                Some(v) => v,
+                None => continue,
+            };
+            if let (Some(key), Some(value)) = (deserialize(&k[..]), deserialize(&v[..])) {
+                return Some((key, value));
+            }
+        }
+        None
+    }
+}
+
+/// Returns iterator over storage entries whose encoded key starts with specified byte prefix.
+///
+/// Entries with empty values and entries which can't be decoded as `(K, V)` are skipped.
+pub fn iter_prefix<K, V>(prefix: &[u8]) -> PrefixIter<K, V>
+where
+    K: DeserializeOwned,
+    V: DeserializeOwned,
+{
+    PrefixIter {
+        raw: raw_keys_with_prefix(prefix),
+        _marker: core::marker::PhantomData,
+    }
+}
+
+/// Position in `StorageOverlay` changes, see `StorageOverlay::savepoint`.
+#[derive(Clone, Copy, Debug)]
+pub struct Savepoint(usize);
+
+/// Write-buffering layer over contract storage.
+///
+/// Writes and deletes are collected in memory and reads are served from the buffer first,
+/// falling back to storage. Nothing reaches storage until `commit` is called, so dropping
+/// the overlay discards all buffered changes.
+///
+/// # Examples
+///
+/// ```
+/// # use power::*;
+/// # #[cfg(feature = "mock")] {
+/// write("c", &3);
+///
+/// let mut overlay = StorageOverlay::new();
+/// overlay.write("a", &1);
+/// overlay.delete("c");
+/// let sp = overlay.savepoint();
+/// overlay.write("b", &2);
+/// assert_eq!(Some(2), overlay.read::<_, u32>("b"));
+/// overlay.rollback_to(sp);
+/// assert_eq!(None, overlay.read::<_, u32>("b"));
+///
+/// // Storage is untouched until commit.
+/// assert_eq!(None, read::<_, u32>("a"));
+/// assert_eq!(Some(3), read::<_, u32>("c"));
+/// overlay.commit();
+/// assert_eq!(Some(1), read::<_, u32>("a"));
+/// assert_eq!(None, read::<_, u32>("b"));
+/// assert!(!has_key("c"));
+/// # }
+/// ```
+pub struct StorageOverlay {
+    journal: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
+impl StorageOverlay {
+    pub fn new() -> StorageOverlay {
+        StorageOverlay {
+            journal: Vec::new(),
+        }
+    }
+
+    fn lookup(&self, key: &[u8]) -> Option<Option<&Vec<u8>>> {
+        self.journal
+            .iter()
+            .rev()
+            .find(|(k, _)| &k[..] == key)
+            .map(|(_, v)| v.as_ref())
+    }
+
+    /// Reads value from the buffer, or from storage if the key wasn't changed.
+    pub fn read<T1, T2>(&self, key: &T1) -> Option<T2>
+    where
+        T1: ?Sized + Serialize,
+        T2: DeserializeOwned,
+    {
+        let k = serialize(key);
+        match self.lookup(&k[..]) {
+            Some(Some(v)) => deserialize(&v[..]),
+            Some(None) => None,
+            None => read_raw(&k[..]).and_then(|v| deserialize(&v[..])),
+        }
+    }
+
+    /// Buffers new or replacing entry.
+    pub fn write<T1, T2>(&mut self, key: &T1, value: &T2)
+    where
+        T1: ?Sized + Serialize,
+        T2: ?Sized + Serialize,
+    {
+        self.journal.push((serialize(key), Some(serialize(value))));
+    }
+
+    /// Buffers entry removal.
+    pub fn delete<T>(&mut self, key: &T)
+    where
+        T: ?Sized + Serialize,
+    {
+        self.journal.push((serialize(key), None));
+    }
+
+    pub fn has_key<T>(&self, key: &T) -> bool
+    where
+        T: ?Sized + Serialize,
+    {
+        let k = serialize(key);
+        match self.lookup(&k[..]) {
+            Some(v) => v.is_some(),
+            None => has_raw_key(&k[..]),
+        }
+    }
+
+    /// Returns true if there are buffered changes.
+    pub fn is_dirty(&self) -> bool {
+        self.journal.len() > 0
+    }
+
+    /// Remembers current state of the buffer to return to it with `rollback_to`.
+    ///
+    /// Savepoints can be nested: rolling back to an outer savepoint also discards changes
+    /// made after all inner ones.
+    pub fn savepoint(&self) -> Savepoint {
+        Savepoint(self.journal.len())
+    }
+
+    /// Discards all changes buffered after the savepoint.
+    pub fn rollback_to(&mut self, savepoint: Savepoint) {
+        self.journal.truncate(savepoint.0);
+    }
+
+    /// Discards all buffered changes.
+    pub fn rollback(&mut self) {
+        self.journal.clear();
+    }
+
+    /// Runs `f` under a new savepoint and rolls back its changes if it returns an error.
+    pub fn atomic<F, R, E>(&mut self, f: F) -> Result<R, E>
+    where
+        F: FnOnce(&mut Self) -> Result<R, E>,
+    {
+        let sp = self.savepoint();
+        let res = f(self);
+        if res.is_err() {
+            self.rollback_to(sp);
+        }
+        res
+    }
+
+    /// Writes all buffered changes to storage.
+    pub fn commit(self) {
+        let mut changes: BTreeMap<Vec<u8>, Option<Vec<u8>>> = BTreeMap::new();
+        for (k, v) in self.journal {
+            changes.insert(k, v);
+        }
+        for (k, v) in changes {
+            match v {
+                Some(v) => write_raw(&k[..], &v[..]),
+                None => delete_raw(&k[..]),
+            }
+        }
     }
 }
 
@@ -87,12 +443,19 @@ fn get_tx_raw() -> Vec<u8> {
 }
 
 pub fn get_tx() -> Option<Tx> {
+    try_get_tx().unwrap_or(None)
+}
+
+/// Decodes current transaction like `get_tx`, but reports why decoding failed.
+///
+/// Returns `Ok(None)` if transaction is unavailable.
+pub fn try_get_tx() -> Result<Option<Tx>, TxDecodeError> {
     let raw = get_tx_raw();
     if raw.len() == 0 {
-        return None;
+        return Ok(None);
     }
-    let v: Value = deserialize(&raw[..]).unwrap();
-    from_value(&v)
+    let v: Value = deserialize(&raw[..]).ok_or(TxDecodeError::NotAMap)?;
+    Tx::try_from(&v).map(Some)
 }
 
 fn get_args_raw() -> Vec<u8> {
@@ -113,6 +476,18 @@ where
     deserialize(&raw[..]).unwrap()
 }
 
+/// Decodes method arguments like `get_args`, but reports which argument failed instead
+/// of trapping.
+///
+/// Returned error can be sent back to the caller with `set_return`.
+pub fn try_get_args<T>() -> Result<T, ArgsError>
+where
+    T: Args,
+{
+    let raw = get_args_raw();
+    T::from_bytes(&raw[..])
+}
+
 fn get_balance_raw() -> Vec<u8> {
     unsafe {
         let size = power_env::get_balance_raw_size();
@@ -123,24 +498,44 @@ fn get_balance_raw() -> Vec<u8> {
     }
 }
 
-pub fn get_balance() -> Vec<Amount> {
+/// Error returned by `try_get_balance`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BalanceError {
+    /// Balance is not a msgpack map.
+    NotAMap,
+    /// Some entry has non-binary currency or non-integer amount.
+    Malformed,
+}
+
+impl Display for BalanceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BalanceError::NotAMap => f.write_str("balance is not a map"),
+            BalanceError::Malformed => f.write_str("balance entry is malformed"),
+        }
+    }
+}
+
+/// Returns contract's balance, traps if VM provided balance which can't be decoded.
+pub fn get_balance() -> Balances {
+    match try_get_balance() {
+        Ok(b) => b,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+/// Returns contract's balance, or error if it can't be decoded.
+///
+/// Empty balance is returned only when VM provided none.
+pub fn try_get_balance() -> Result<Balances, BalanceError> {
     let raw = get_balance_raw();
     if raw.len() == 0 {
-        return vec![];
+        return Ok(Balances::new());
     }
-    let v: Value = deserialize(&raw[..]).unwrap();
-
-    if let Value::Map(balance) = v {
-        let mut res = Vec::with_capacity(balance.len());
-        for (k, v) in balance {
-            res.push(Amount {
-                currency: k.as_slice().unwrap().to_vec(),
-                amount: v.as_u64().unwrap(),
-            });
-        }
-        return res;
+    match deserialize::<Value>(&raw[..]) {
+        Some(ref v) if v.is_map() => from_value(v).ok_or(BalanceError::Malformed),
+        _ => Err(BalanceError::NotAMap),
     }
-    vec![]
 }
 
 pub fn set_return<T>(ret: T)
@@ -263,6 +658,24 @@ impl TxEmitter {
     }
 }
 
+/// Starts deploy transaction for a child contract, e.g. from a factory contract.
+///
+/// Use `DeployTx::state` to set initial storage and `DeployTx::call` to call constructor,
+/// then `DeployTx::emit` to send it.
+///
+/// # Examples
+///
+/// ```ignore
+/// deploy_contract(include_bytes!("child.wasm"), VmType::Wasm)
+///     .state("owner", &owner)
+///     .call("init", vec![supply.into()])
+///     .emit()
+///     .unwrap();
+/// ```
+pub fn deploy_contract(code: &[u8], vm: VmType) -> DeployTx {
+    DeployTx::new(code, vm)
+}
+
 #[derive(Serialize)]
 struct TxCall<'a, T> {
     k: u64,
@@ -32,8 +32,10 @@ pub extern crate crc;
 
 extern crate power_env;
 
+extern crate power_address;
+
 extern crate power_derive;
-pub use power_derive::power_method;
+pub use power_derive::{address, power_method};
 
 pub mod types;
 pub use crate::types::*;
@@ -44,6 +46,21 @@ pub use crate::value::*;
 pub mod env;
 pub use crate::env::*;
 
+pub mod args;
+pub use crate::args::*;
+
+pub mod decimal;
+pub use crate::decimal::*;
+
+pub mod rand;
+pub use crate::rand::*;
+
+pub mod time;
+pub use crate::time::*;
+
+pub mod tx;
+pub use crate::tx::*;
+
 pub mod io;
 pub use crate::io::*;
 
@@ -52,3 +69,15 @@ pub use crate::ser::*;
 
 pub mod de;
 pub use crate::de::*;
+
+pub mod storage;
+pub use crate::storage::*;
+
+pub mod migrate;
+pub use crate::migrate::*;
+
+pub mod scheduler;
+pub use crate::scheduler::*;
+
+#[cfg(feature = "mock")]
+pub mod mock;
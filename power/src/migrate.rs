@@ -0,0 +1,187 @@
+use crate::*;
+
+/// Storage key holding current schema version.
+pub const SCHEMA_VERSION_KEY: &str = "$schema_version";
+
+/// Returns schema version of contract storage, 0 if it was never set.
+pub fn schema_version() -> u64 {
+    read(SCHEMA_VERSION_KEY).unwrap_or(0)
+}
+
+pub fn set_schema_version(version: u64) {
+    write(SCHEMA_VERSION_KEY, &version);
+}
+
+/// Function upgrading storage from one schema version to the next one.
+pub type Migration = fn();
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MigrationError {
+    /// Storage has newer schema than the contract knows about.
+    Downgrade { stored: u64, latest: u64 },
+}
+
+impl Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MigrationError::Downgrade { stored, latest } => write!(
+                f,
+                "storage schema version {} is newer than latest known version {}",
+                stored, latest
+            ),
+        }
+    }
+}
+
+/// Ordered list of storage migrations.
+///
+/// Migration at index `n` upgrades storage from version `n` to version `n + 1`, so the
+/// latest schema version is the number of migrations.
+///
+/// # Examples
+///
+/// ```
+/// # use power::*;
+/// # #[cfg(feature = "mock")] {
+/// // Version 1 keeps price in cents instead of whole units.
+/// fn v0_to_v1() {
+///     migrate_entry("price", |p: u32| p as u64 * 100);
+/// }
+///
+/// fn v1_to_v2() {
+///     write("fee", &5u64);
+/// }
+///
+/// const MIGRATIONS: Migrations = Migrations::new(&[v0_to_v1, v1_to_v2]);
+///
+/// write("price", &12u32);
+/// assert!(MIGRATIONS.is_pending());
+/// assert_eq!(Ok(2), MIGRATIONS.run());
+/// assert_eq!(Some(1200u64), read("price"));
+/// assert_eq!(Some(5u64), read("fee"));
+///
+/// // Nothing to do at the latest version.
+/// assert_eq!(Ok(2), MIGRATIONS.ensure());
+/// assert_eq!(Some(1200u64), read("price"));
+///
+/// set_schema_version(3);
+/// assert_eq!(
+///     Err(MigrationError::Downgrade { stored: 3, latest: 2 }),
+///     MIGRATIONS.run()
+/// );
+/// # }
+/// ```
+pub struct Migrations {
+    steps: &'static [Migration],
+}
+
+impl Migrations {
+    pub const fn new(steps: &'static [Migration]) -> Self {
+        Migrations { steps: steps }
+    }
+
+    /// Returns schema version produced by the last migration.
+    pub fn latest(&self) -> u64 {
+        self.steps.len() as u64
+    }
+
+    /// Returns true if stored schema is older than the latest one.
+    pub fn is_pending(&self) -> bool {
+        schema_version() < self.latest()
+    }
+
+    /// Runs all migrations newer than stored schema version and returns resulting version.
+    ///
+    /// Schema version is updated after every step.
+    pub fn run(&self) -> Result<u64, MigrationError> {
+        let stored = schema_version();
+        let latest = self.latest();
+        if stored > latest {
+            return Err(MigrationError::Downgrade {
+                stored: stored,
+                latest: latest,
+            });
+        }
+        for version in stored..latest {
+            (self.steps[version as usize])();
+            set_schema_version(version + 1);
+        }
+        Ok(latest)
+    }
+
+    /// Runs pending migrations, if any.
+    ///
+    /// Intended to be called at the beginning of every method for lazy upgrades.
+    pub fn ensure(&self) -> Result<u64, MigrationError> {
+        if self.is_pending() {
+            self.run()
+        } else {
+            Ok(schema_version())
+        }
+    }
+}
+
+/// Re-encodes value stored under the key.
+///
+/// `f` receives stored value and returns new one, or `None` to delete the entry.
+///
+/// Returns false if there is no such entry or it can't be decoded.
+pub fn reencode<K, F>(key: &K, f: F) -> bool
+where
+    K: ?Sized + Serialize,
+    F: FnOnce(Value) -> Option<Value>,
+{
+    let k = serialize(key);
+    reencode_raw(&k[..], f)
+}
+
+/// Converts value stored under the key from `Old` type to `New` type.
+///
+/// Returns false if there is no such entry or it can't be decoded as `Old`.
+pub fn migrate_entry<K, Old, New, F>(key: &K, f: F) -> bool
+where
+    K: ?Sized + Serialize,
+    Old: DeserializeOwned,
+    New: Serialize,
+    F: FnOnce(Old) -> New,
+{
+    match read::<K, Old>(key) {
+        Some(old) => {
+            write(key, &f(old));
+            true
+        }
+        None => false,
+    }
+}
+
+/// Re-encodes all values whose encoded key starts with specified byte prefix.
+///
+/// See `reencode` for meaning of `f`. Returns number of re-encoded entries.
+pub fn reencode_prefix<F>(prefix: &[u8], mut f: F) -> usize
+where
+    F: FnMut(Value) -> Option<Value>,
+{
+    let keys: Vec<Vec<u8>> = raw_keys_with_prefix(prefix).collect();
+    let mut count = 0;
+    for k in keys {
+        if reencode_raw(&k[..], &mut f) {
+            count += 1;
+        }
+    }
+    count
+}
+
+fn reencode_raw<F>(key: &[u8], f: F) -> bool
+where
+    F: FnOnce(Value) -> Option<Value>,
+{
+    let old: Value = match read_raw(key).and_then(|v| deserialize(&v[..])) {
+        Some(v) => v,
+        None => return false,
+    };
+    match f(old) {
+        Some(new) => write_raw(key, &serialize(new)[..]),
+        None => delete_raw(key),
+    }
+    true
+}
@@ -0,0 +1,40 @@
+//! Typed helpers over `power_env::mock` for testing contracts natively.
+//!
+//! Available with `mock` feature only.
+
+use crate::*;
+
+pub use power_env::mock::{reset, set_mean_time, set_storage_entry, storage, take_debug_log};
+
+/// Sets incoming transaction returned by `get_tx`.
+pub fn set_tx<T: Into<Value>>(tx: T) {
+    power_env::mock::set_tx(serialize(tx.into()));
+}
+
+/// Sets arguments returned by `get_args`.
+pub fn set_args<T: Serialize>(args: T) {
+    power_env::mock::set_args(serialize(args));
+}
+
+/// Sets balances returned by `get_balance`.
+pub fn set_balance(balance: &Balances) {
+    power_env::mock::set_balance(serialize(Value::from(balance)));
+}
+
+/// Sets entropy returned by `get_entropy`.
+pub fn set_entropy<T: Into<Vec<u8>>>(entropy: T) {
+    power_env::mock::set_entropy(entropy.into());
+}
+
+/// Returns value passed to the last `set_return` call.
+pub fn take_return<T: DeserializeOwned>() -> Option<T> {
+    power_env::mock::take_return().and_then(|r| deserialize(&r[..]))
+}
+
+/// Returns transactions emitted so far.
+pub fn take_emitted() -> Vec<Value> {
+    power_env::mock::take_emitted()
+        .iter()
+        .filter_map(|t| deserialize(&t[..]))
+        .collect()
+}
@@ -0,0 +1,241 @@
+use crate::*;
+
+/// ChaCha20 block function producing 16 output words.
+fn chacha20_block(key: &[u32; 8], counter: u64, out: &mut [u32; 16]) {
+    let mut state: [u32; 16] = [
+        0x61707865,
+        0x3320646e,
+        0x79622d32,
+        0x6b206574,
+        key[0],
+        key[1],
+        key[2],
+        key[3],
+        key[4],
+        key[5],
+        key[6],
+        key[7],
+        counter as u32,
+        (counter >> 32) as u32,
+        0,
+        0,
+    ];
+    let init = state;
+
+    fn qr(s: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+        s[a] = s[a].wrapping_add(s[b]);
+        s[d] = (s[d] ^ s[a]).rotate_left(16);
+        s[c] = s[c].wrapping_add(s[d]);
+        s[b] = (s[b] ^ s[c]).rotate_left(12);
+        s[a] = s[a].wrapping_add(s[b]);
+        s[d] = (s[d] ^ s[a]).rotate_left(8);
+        s[c] = s[c].wrapping_add(s[d]);
+        s[b] = (s[b] ^ s[c]).rotate_left(7);
+    }
+
+    for _ in 0..10 {
+        qr(&mut state, 0, 4, 8, 12);
+        qr(&mut state, 1, 5, 9, 13);
+        qr(&mut state, 2, 6, 10, 14);
+        qr(&mut state, 3, 7, 11, 15);
+        qr(&mut state, 0, 5, 10, 15);
+        qr(&mut state, 1, 6, 11, 12);
+        qr(&mut state, 2, 7, 8, 13);
+        qr(&mut state, 3, 4, 9, 14);
+    }
+    for i in 0..16 {
+        out[i] = state[i].wrapping_add(init[i]);
+    }
+}
+
+/// Deterministic ChaCha20-based random number generator.
+///
+/// The same seed always produces the same sequence, so every node executing a contract
+/// gets the same results when the generator is seeded from `get_entropy`.
+///
+/// # Examples
+///
+/// ```
+/// # use power::*;
+/// # #[cfg(feature = "mock")] {
+/// # power::mock::set_entropy(&[7; 32][..]);
+/// let participants = ["alice", "bob", "carol"];
+/// let mut rng = Rng::from_entropy().expect("no entropy");
+/// let winner = rng.choose(&participants).unwrap();
+/// let dice = rng.gen_range(1, 7);
+/// assert!(participants.contains(winner));
+/// assert!(dice >= 1 && dice < 7);
+/// # }
+/// ```
+pub struct Rng {
+    key: [u32; 8],
+    counter: u64,
+    buffer: [u32; 16],
+    index: usize,
+}
+
+impl Rng {
+    /// Creates generator with ChaCha20 key `seed`, nonce and counter starting from zero.
+    ///
+    /// # Examples
+    ///
+    /// Keystream for zero key matches ChaCha20 test vector:
+    ///
+    /// ```
+    /// # use power::*;
+    /// let mut rng = Rng::from_seed([0; 32]);
+    /// let mut block = [0u8; 32];
+    /// rng.fill_bytes(&mut block);
+    /// let hex: String = block.iter().map(|b| format!("{:02x}", b)).collect();
+    /// assert_eq!("76b8e0ada0f13d90405d6ae55386bd28bdd219b8a08ded1aa836efcc8b770dc7", hex);
+    /// ```
+    pub fn from_seed(seed: [u8; 32]) -> Rng {
+        let mut key = [0u32; 8];
+        for i in 0..8 {
+            key[i] = u32::from_le_bytes([
+                seed[i * 4],
+                seed[i * 4 + 1],
+                seed[i * 4 + 2],
+                seed[i * 4 + 3],
+            ]);
+        }
+        Rng {
+            key: key,
+            counter: 0,
+            buffer: [0; 16],
+            index: 16,
+        }
+    }
+
+    /// Creates generator seeded with arbitrary number of bytes.
+    ///
+    /// Bytes beyond 32 are folded into the seed by XOR after passing through ChaCha, so
+    /// every input byte affects the output.
+    pub fn from_bytes(bytes: &[u8]) -> Rng {
+        let mut seed = [0u8; 32];
+        for (i, chunk) in bytes.chunks(32).enumerate() {
+            let mut block = [0u8; 32];
+            block[..chunk.len()].copy_from_slice(chunk);
+            if i > 0 {
+                // Mix the seed accumulated so far before adding next chunk.
+                let mut rng = Rng::from_seed(seed);
+                rng.fill_bytes(&mut seed);
+            }
+            for j in 0..32 {
+                seed[j] ^= block[j];
+            }
+        }
+        let mut seed_len = [0u8; 32];
+        seed_len[..8].copy_from_slice(&(bytes.len() as u64).to_le_bytes());
+        let mut rng = Rng::from_seed(seed);
+        rng.fill_bytes(&mut seed);
+        for j in 0..32 {
+            seed[j] ^= seed_len[j];
+        }
+        Rng::from_seed(seed)
+    }
+
+    /// Creates generator seeded with block entropy provided by VM.
+    ///
+    /// Returns `None` if VM provides no entropy.
+    pub fn from_entropy() -> Option<Rng> {
+        let entropy = get_entropy();
+        if entropy.len() == 0 {
+            return None;
+        }
+        Some(Rng::from_bytes(&entropy[..]))
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        if self.index >= 16 {
+            chacha20_block(&self.key, self.counter, &mut self.buffer);
+            self.counter = self.counter.wrapping_add(1);
+            self.index = 0;
+        }
+        let x = self.buffer[self.index];
+        self.index += 1;
+        x
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let lo = self.next_u32() as u64;
+        let hi = self.next_u32() as u64;
+        (hi << 32) | lo
+    }
+
+    pub fn fill_bytes(&mut self, dst: &mut [u8]) {
+        for chunk in dst.chunks_mut(4) {
+            let x = self.next_u32().to_le_bytes();
+            chunk.copy_from_slice(&x[..chunk.len()]);
+        }
+    }
+
+    /// Returns uniformly distributed number in `[0, n)`, or 0 if `n` is 0.
+    ///
+    /// Uses rejection sampling, so there is no modulo bias.
+    pub fn gen_below(&mut self, n: u64) -> u64 {
+        if n == 0 {
+            return 0;
+        }
+        // Largest multiple of n which fits in u64, values above it are rejected.
+        let zone = core::u64::MAX - (core::u64::MAX % n + 1) % n;
+        loop {
+            let x = self.next_u64();
+            if x <= zone {
+                return x % n;
+            }
+        }
+    }
+
+    /// Returns uniformly distributed number in `[low, high)`.
+    ///
+    /// Returns `low` if the range is empty.
+    pub fn gen_range(&mut self, low: u64, high: u64) -> u64 {
+        if high <= low {
+            return low;
+        }
+        low + self.gen_below(high - low)
+    }
+
+    /// Returns true with probability `numerator / denominator`.
+    pub fn gen_ratio(&mut self, numerator: u64, denominator: u64) -> bool {
+        self.gen_below(denominator) < numerator
+    }
+
+    /// Shuffles slice in place using Fisher-Yates algorithm.
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            let j = self.gen_below(i as u64 + 1) as usize;
+            slice.swap(i, j);
+        }
+    }
+
+    /// Returns random element of the slice, or `None` if it is empty.
+    pub fn choose<'a, T>(&mut self, slice: &'a [T]) -> Option<&'a T> {
+        if slice.len() == 0 {
+            return None;
+        }
+        slice.get(self.gen_below(slice.len() as u64) as usize)
+    }
+
+    /// Returns random index with probability proportional to its weight.
+    ///
+    /// Returns `None` if weights are empty, all zero or their sum overflows.
+    pub fn choose_weighted(&mut self, weights: &[u64]) -> Option<usize> {
+        let mut total: u64 = 0;
+        for w in weights {
+            total = total.checked_add(*w)?;
+        }
+        if total == 0 {
+            return None;
+        }
+        let mut x = self.gen_below(total);
+        for (i, w) in weights.iter().enumerate() {
+            if x < *w {
+                return Some(i);
+            }
+            x -= *w;
+        }
+        None
+    }
+}
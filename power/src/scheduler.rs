@@ -0,0 +1,221 @@
+use crate::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SchedulerError {
+    /// Address of the contract is unknown because there is no incoming transaction.
+    NoAddress,
+    /// There is no pending job with such id.
+    UnknownJob(u64),
+    /// Incoming transaction was not emitted by this scheduler.
+    NotScheduled,
+    /// Incoming transaction belongs to a job which was cancelled, rescheduled or has
+    /// already run.
+    Stale(u64),
+    Build(TxBuildError),
+}
+
+impl Display for SchedulerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SchedulerError::NoAddress => f.write_str("contract address is unknown"),
+            SchedulerError::UnknownJob(id) => write!(f, "job {} is not scheduled", id),
+            SchedulerError::NotScheduled => f.write_str("transaction is not a scheduled call"),
+            SchedulerError::Stale(id) => write!(f, "job {} is stale", id),
+            SchedulerError::Build(e) => Display::fmt(e, f),
+        }
+    }
+}
+
+impl From<TxBuildError> for SchedulerError {
+    fn from(e: TxBuildError) -> Self {
+        SchedulerError::Build(e)
+    }
+}
+
+/// Pending call of contract's own method.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Job {
+    pub id: u64,
+    pub method: String,
+    pub args: Vec<Value>,
+    /// Earliest time of execution.
+    pub at: Timestamp,
+    /// Incremented on every reschedule, so transactions emitted earlier become stale.
+    pub generation: u64,
+}
+
+/// Stored form of `Job`: method, args, time in milliseconds and generation.
+type StoredJob = (String, Vec<Value>, u64, u64);
+
+/// Schedules calls of contract's own methods at specified time.
+///
+/// Every job is stored under `(prefix, id)` key and executed by a self-call transaction
+/// with `not_before` set. The transaction carries `[id, generation]` in extradata under
+/// the `prefix` key, and the called method must pass it to `begin`, which rejects calls
+/// of cancelled or rescheduled jobs.
+///
+/// # Examples
+///
+/// ```
+/// # use power::*;
+/// # #[cfg(feature = "mock")] {
+/// # use power::mock::{set_tx, take_emitted};
+/// const SCHEDULER: Scheduler = Scheduler::new("sched");
+///
+/// let me = Address::from_parts(1, 1, 5).unwrap();
+/// let user = Address::from_parts(1, 1, 6).unwrap();
+/// # let call = |from: &Address| {
+/// #     Value::Map(vec![
+/// #         ("k".into(), 16u64.into()),
+/// #         ("f".into(), from.into()),
+/// #         ("to".into(), (&me).into()),
+/// #     ])
+/// # };
+/// # // Node executes emitted self-call with contract's address as the sender.
+/// # let deliver = |tx: &Value| {
+/// #     let mut m = tx.as_map().unwrap().clone();
+/// #     m.push(("f".into(), (&me).into()));
+/// #     set_tx(Value::Map(m));
+/// # };
+/// let start = Timestamp::from_millis(1_700_000_000_000);
+/// let end = start.checked_add(Duration::from_days(1).unwrap()).unwrap();
+///
+/// // `user` calls method starting an auction.
+/// set_tx(call(&user));
+/// let id = SCHEDULER.schedule("finish_auction", vec![], end).unwrap();
+/// let first = take_emitted();
+///
+/// // Moving the job makes the first transaction stale.
+/// let later = end.checked_add(Duration::from_hours(1).unwrap()).unwrap();
+/// SCHEDULER.reschedule(id, later).unwrap();
+/// let second = take_emitted();
+/// deliver(&first[0]);
+/// assert_eq!(Err(SchedulerError::Stale(id)), SCHEDULER.begin());
+///
+/// // `finish_auction` called by the second transaction runs the job, once.
+/// deliver(&second[0]);
+/// assert_eq!("finish_auction", SCHEDULER.begin().unwrap().method);
+/// assert_eq!(Err(SchedulerError::Stale(id)), SCHEDULER.begin());
+///
+/// // Cancelled job doesn't run.
+/// set_tx(call(&user));
+/// let id = SCHEDULER.schedule("finish_auction", vec![], start).unwrap();
+/// let third = take_emitted();
+/// assert!(SCHEDULER.cancel(id));
+/// deliver(&third[0]);
+/// assert_eq!(Err(SchedulerError::Stale(id)), SCHEDULER.begin());
+///
+/// // Other senders can't pretend to be a scheduled call.
+/// set_tx(call(&user));
+/// assert_eq!(Err(SchedulerError::NotScheduled), SCHEDULER.begin());
+/// # }
+/// ```
+pub struct Scheduler {
+    prefix: &'static str,
+}
+
+impl Scheduler {
+    /// Creates scheduler with specified key prefix.
+    pub const fn new(prefix: &'static str) -> Self {
+        Scheduler { prefix: prefix }
+    }
+
+    fn jobs(&self) -> StorageMap<u64, StoredJob> {
+        StorageMap::new(self.prefix)
+    }
+
+    /// Returns pending job.
+    pub fn get(&self, id: u64) -> Option<Job> {
+        self.jobs().get(&id).map(|(method, args, at, generation)| Job {
+            id: id,
+            method: method,
+            args: args,
+            at: Timestamp::from_millis(at),
+            generation: generation,
+        })
+    }
+
+    /// Returns ids of pending jobs.
+    pub fn pending(&self) -> Vec<u64> {
+        self.jobs().keys().collect()
+    }
+
+    fn store(&self, job: &Job) {
+        let stored = (job.method.clone(), job.args.clone(), job.at.as_millis(), job.generation);
+        self.jobs().insert(&job.id, &stored);
+    }
+
+    /// Emits self-call transaction executing the job.
+    fn emit(&self, job: &Job) -> Result<(), SchedulerError> {
+        let to = get_tx().and_then(|tx| tx.to).ok_or(SchedulerError::NoAddress)?;
+        let tag = Value::Array(vec![job.id.into(), job.generation.into()]);
+        GenericTx::new(to)
+            .call(&job.method, job.args.clone())
+            .not_before(job.at.as_millis())
+            .extra(self.prefix, tag)
+            .emit()?;
+        Ok(())
+    }
+
+    /// Schedules call of the method at specified time and returns job id.
+    pub fn schedule(
+        &self,
+        method: &str,
+        args: Vec<Value>,
+        at: Timestamp,
+    ) -> Result<u64, SchedulerError> {
+        let id: u64 = read(&(self.prefix, "next_id")).unwrap_or(0);
+        let job = Job {
+            id: id,
+            method: method.to_string(),
+            args: args,
+            at: at,
+            generation: 0,
+        };
+        self.emit(&job)?;
+        self.store(&job);
+        write(&(self.prefix, "next_id"), &(id + 1));
+        Ok(id)
+    }
+
+    /// Moves the job to another time, transaction emitted earlier becomes stale.
+    pub fn reschedule(&self, id: u64, at: Timestamp) -> Result<(), SchedulerError> {
+        let mut job = self.get(id).ok_or(SchedulerError::UnknownJob(id))?;
+        job.at = at;
+        job.generation += 1;
+        self.emit(&job)?;
+        self.store(&job);
+        Ok(())
+    }
+
+    /// Cancels the job, returns false if there is no such pending job.
+    pub fn cancel(&self, id: u64) -> bool {
+        self.jobs().remove(&id).is_some()
+    }
+
+    /// Checks that incoming transaction is the current execution of a pending job, removes
+    /// the job and returns it.
+    ///
+    /// Must be called by scheduled methods before doing anything else.
+    pub fn begin(&self) -> Result<Job, SchedulerError> {
+        let tx = get_tx().ok_or(SchedulerError::NotScheduled)?;
+        // Only the contract itself can emit transactions from its address.
+        if tx.to != Some(tx.from) {
+            return Err(SchedulerError::NotScheduled);
+        }
+        let tag = match tx.extradata.get(self.prefix) {
+            Some(Value::Array(tag)) if tag.len() == 2 => tag,
+            _ => return Err(SchedulerError::NotScheduled),
+        };
+        let (id, generation) = match (tag[0].as_u64(), tag[1].as_u64()) {
+            (Some(id), Some(generation)) => (id, generation),
+            _ => return Err(SchedulerError::NotScheduled),
+        };
+        let job = self.get(id).ok_or(SchedulerError::Stale(id))?;
+        if job.generation != generation {
+            return Err(SchedulerError::Stale(id));
+        }
+        self.jobs().remove(&id);
+        Ok(job)
+    }
+}
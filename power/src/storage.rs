@@ -0,0 +1,369 @@
+use crate::*;
+
+use core::marker::PhantomData;
+
+/// Typed key-value collection stored in contract storage.
+///
+/// Every entry is stored under the `(prefix, key)` tuple, so maps with different prefixes
+/// never collide with each other.
+///
+/// # Examples
+///
+/// ```
+/// # use power::*;
+/// # #[cfg(feature = "mock")] {
+/// const BALANCES: StorageMap<Address, u64> = StorageMap::new("balances");
+/// let addr = Address::from_parts(1, 1, 5).unwrap();
+///
+/// BALANCES.insert(&addr, &100);
+/// assert_eq!(Some(100), BALANCES.get(&addr));
+///
+/// BALANCES.entry(&addr).and_modify(|b| *b += 20);
+/// assert_eq!(150, BALANCES.entry(&addr).and_modify(|b| *b += 30).or_insert(0));
+///
+/// assert_eq!(Some(150), BALANCES.remove(&addr));
+/// assert!(!BALANCES.contains(&addr));
+/// assert_eq!(0, BALANCES.entry(&addr).or_default());
+/// assert_eq!(Some(0), BALANCES.get(&addr));
+/// # }
+/// ```
+pub struct StorageMap<K, V> {
+    prefix: &'static str,
+    _marker: PhantomData<(K, V)>,
+}
+
+impl<K, V> StorageMap<K, V>
+where
+    K: Serialize,
+    V: Serialize + DeserializeOwned,
+{
+    /// Creates map with specified key prefix.
+    pub const fn new(prefix: &'static str) -> Self {
+        StorageMap {
+            prefix: prefix,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns key prefix of this map.
+    pub fn prefix(&self) -> &'static str {
+        self.prefix
+    }
+
+    /// Returns value stored under the key, or `None` if there is no such entry.
+    pub fn get(&self, key: &K) -> Option<V> {
+        read(&(self.prefix, key))
+    }
+
+    /// Creates new or replaces existing entry.
+    pub fn insert(&self, key: &K, value: &V) {
+        write(&(self.prefix, key), value);
+    }
+
+    /// Removes entry and returns its previous value.
+    pub fn remove(&self, key: &K) -> Option<V> {
+        let old = self.get(key);
+        delete(&(self.prefix, key));
+        old
+    }
+
+    /// Returns true if there is an entry for the key.
+    pub fn contains(&self, key: &K) -> bool {
+        has_key(&(self.prefix, key))
+    }
+
+    /// Returns entry for in-place manipulation.
+    pub fn entry<'a>(&'a self, key: &'a K) -> Entry<'a, K, V> {
+        Entry {
+            map: self,
+            key: key,
+        }
+    }
+}
+
+impl<K, V> StorageMap<K, V>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+{
+    /// Returns byte prefix shared by encoded keys of all entries of this map.
+    fn key_prefix(&self) -> Vec<u8> {
+        // Keys are encoded as two-element msgpack arrays (fixarray marker 0x92) starting
+        // with the prefix string.
+        let mut p = vec![0x92];
+        p.extend(serialize(self.prefix));
+        p
+    }
+
+    /// Returns iterator over keys of the map in encoded key order.
+    pub fn keys(&self) -> impl Iterator<Item = K> {
+        keys_with_prefix::<(String, K)>(&self.key_prefix()[..]).map(|(_, k)| k)
+    }
+
+    /// Returns iterator over entries of the map in encoded key order.
+    pub fn iter(&self) -> impl Iterator<Item = (K, V)> {
+        iter_prefix::<(String, K), V>(&self.key_prefix()[..]).map(|((_, k), v)| (k, v))
+    }
+
+    /// Converts every value of the map from `Old` type, e.g. from previous schema version.
+    ///
+    /// Values which can't be decoded as `Old` are left untouched. Returns number of
+    /// converted entries.
+    pub fn migrate_values<Old, F>(&self, mut f: F) -> usize
+    where
+        Old: DeserializeOwned,
+        F: FnMut(Old) -> V,
+    {
+        let keys: Vec<K> = self.keys().collect();
+        let mut count = 0;
+        for k in keys {
+            if migrate_entry(&(self.prefix, &k), &mut f) {
+                count += 1;
+            }
+        }
+        count
+    }
+}
+
+/// Single entry of `StorageMap`, see `StorageMap::entry`.
+pub struct Entry<'a, K, V> {
+    map: &'a StorageMap<K, V>,
+    key: &'a K,
+}
+
+impl<'a, K, V> Entry<'a, K, V>
+where
+    K: Serialize,
+    V: Serialize + DeserializeOwned,
+{
+    /// Returns current value of the entry.
+    pub fn get(&self) -> Option<V> {
+        self.map.get(self.key)
+    }
+
+    /// Returns true if the entry exists.
+    pub fn exists(&self) -> bool {
+        self.map.contains(self.key)
+    }
+
+    /// Replaces value of the entry.
+    pub fn insert(&self, value: &V) {
+        self.map.insert(self.key, value);
+    }
+
+    /// Removes the entry and returns its previous value.
+    pub fn remove(self) -> Option<V> {
+        self.map.remove(self.key)
+    }
+
+    /// Returns current value, storing `default` first if the entry is empty.
+    pub fn or_insert(self, default: V) -> V {
+        self.or_insert_with(|| default)
+    }
+
+    /// Returns current value, storing result of `f` first if the entry is empty.
+    pub fn or_insert_with<F>(self, f: F) -> V
+    where
+        F: FnOnce() -> V,
+    {
+        match self.get() {
+            Some(v) => v,
+            None => {
+                let v = f();
+                self.insert(&v);
+                v
+            }
+        }
+    }
+
+    /// Applies `f` to the value and stores the result if the entry exists.
+    pub fn and_modify<F>(self, f: F) -> Self
+    where
+        F: FnOnce(&mut V),
+    {
+        if let Some(mut v) = self.get() {
+            f(&mut v);
+            self.insert(&v);
+        }
+        self
+    }
+}
+
+impl<'a, K, V> Entry<'a, K, V>
+where
+    K: Serialize,
+    V: Serialize + DeserializeOwned + Default,
+{
+    /// Returns current value, storing `V::default()` first if the entry is empty.
+    pub fn or_default(self) -> V {
+        self.or_insert_with(V::default)
+    }
+}
+
+/// Typed list stored in contract storage.
+///
+/// Length is stored under the `(prefix, "len")` key and every element under its own
+/// `(prefix, index)` key, so appending or updating an element rewrites only that element.
+///
+/// # Examples
+///
+/// ```
+/// # use power::*;
+/// # #[cfg(feature = "mock")] {
+/// const HOLDERS: StorageVec<u32> = StorageVec::new("holders");
+///
+/// HOLDERS.push(&10);
+/// HOLDERS.push(&20);
+/// HOLDERS.push(&30);
+/// assert_eq!(Some(30), HOLDERS.get(HOLDERS.len() - 1));
+///
+/// // Last element takes place of the removed one.
+/// assert_eq!(Some(10), HOLDERS.swap_remove(0));
+/// assert_eq!(vec![30, 20], HOLDERS.iter().collect::<Vec<_>>());
+/// assert_eq!(None, HOLDERS.swap_remove(2));
+///
+/// assert_eq!(Some(20), HOLDERS.pop());
+/// assert_eq!(Some(30), HOLDERS.pop());
+/// assert_eq!(None, HOLDERS.pop());
+/// assert!(HOLDERS.is_empty());
+/// # }
+/// ```
+pub struct StorageVec<T> {
+    prefix: &'static str,
+    _marker: PhantomData<T>,
+}
+
+impl<T> StorageVec<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Creates list with specified key prefix.
+    pub const fn new(prefix: &'static str) -> Self {
+        StorageVec {
+            prefix: prefix,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns key prefix of this list.
+    pub fn prefix(&self) -> &'static str {
+        self.prefix
+    }
+
+    /// Returns number of elements in the list.
+    pub fn len(&self) -> u64 {
+        read(&(self.prefix, "len")).unwrap_or(0)
+    }
+
+    /// Returns true if the list contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn set_len(&self, len: u64) {
+        if len == 0 {
+            delete(&(self.prefix, "len"));
+        } else {
+            write(&(self.prefix, "len"), &len);
+        }
+    }
+
+    /// Returns element at the index, or `None` if the index is out of bounds.
+    pub fn get(&self, index: u64) -> Option<T> {
+        if index >= self.len() {
+            return None;
+        }
+        read(&(self.prefix, index))
+    }
+
+    /// Replaces element at the index.
+    ///
+    /// Returns false if the index is out of bounds.
+    pub fn set(&self, index: u64, value: &T) -> bool {
+        if index >= self.len() {
+            return false;
+        }
+        write(&(self.prefix, index), value);
+        true
+    }
+
+    /// Appends element to the end of the list.
+    pub fn push(&self, value: &T) {
+        let len = self.len();
+        write(&(self.prefix, len), value);
+        self.set_len(len + 1);
+    }
+
+    /// Removes the last element and returns it, or `None` if the list is empty.
+    pub fn pop(&self) -> Option<T> {
+        let len = self.len();
+        if len == 0 {
+            return None;
+        }
+        let last = read(&(self.prefix, len - 1));
+        delete(&(self.prefix, len - 1));
+        self.set_len(len - 1);
+        last
+    }
+
+    /// Removes element at the index and returns it, replacing it with the last element.
+    ///
+    /// Does not preserve ordering, but takes O(1) storage operations.
+    pub fn swap_remove(&self, index: u64) -> Option<T> {
+        let len = self.len();
+        if index >= len {
+            return None;
+        }
+        let removed = read(&(self.prefix, index));
+        if index != len - 1 {
+            if let Some(last) = read::<_, T>(&(self.prefix, len - 1)) {
+                write(&(self.prefix, index), &last);
+            }
+        }
+        delete(&(self.prefix, len - 1));
+        self.set_len(len - 1);
+        removed
+    }
+
+    /// Removes all elements.
+    pub fn clear(&self) {
+        for i in 0..self.len() {
+            delete(&(self.prefix, i));
+        }
+        self.set_len(0);
+    }
+
+    /// Returns iterator over elements of the list.
+    pub fn iter(&self) -> StorageVecIter<'_, T> {
+        StorageVecIter {
+            vec: self,
+            index: 0,
+            len: self.len(),
+        }
+    }
+}
+
+/// Iterator over `StorageVec` elements, see `StorageVec::iter`.
+pub struct StorageVecIter<'a, T> {
+    vec: &'a StorageVec<T>,
+    index: u64,
+    len: u64,
+}
+
+impl<'a, T> Iterator for StorageVecIter<'a, T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        while self.index < self.len {
+            let i = self.index;
+            self.index += 1;
+            if let Some(x) = read(&(self.vec.prefix, i)) {
+                return Some(x);
+            }
+        }
+        None
+    }
+}
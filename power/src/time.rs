@@ -0,0 +1,480 @@
+use crate::*;
+
+const MS_PER_SECOND: u64 = 1000;
+const MS_PER_MINUTE: u64 = 60 * MS_PER_SECOND;
+const MS_PER_HOUR: u64 = 60 * MS_PER_MINUTE;
+const MS_PER_DAY: u64 = 24 * MS_PER_HOUR;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimeError {
+    /// Text is not a valid ISO-8601 date/time or a date field is out of range.
+    Invalid,
+    /// Time is before the Unix epoch or doesn't fit in `u64` milliseconds.
+    OutOfRange,
+}
+
+impl Display for TimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimeError::Invalid => f.write_str("invalid date/time"),
+            TimeError::OutOfRange => f.write_str("date/time out of range"),
+        }
+    }
+}
+
+/// Length of time interval in milliseconds.
+///
+/// Constructors taking larger units return `None` if the duration doesn't fit in `u64`
+/// milliseconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Duration(u64);
+
+impl Duration {
+    pub const fn from_millis(ms: u64) -> Duration {
+        Duration(ms)
+    }
+
+    pub const fn from_secs(secs: u64) -> Option<Duration> {
+        match secs.checked_mul(MS_PER_SECOND) {
+            Some(ms) => Some(Duration(ms)),
+            None => None,
+        }
+    }
+
+    pub const fn from_minutes(minutes: u64) -> Option<Duration> {
+        match minutes.checked_mul(MS_PER_MINUTE) {
+            Some(ms) => Some(Duration(ms)),
+            None => None,
+        }
+    }
+
+    pub const fn from_hours(hours: u64) -> Option<Duration> {
+        match hours.checked_mul(MS_PER_HOUR) {
+            Some(ms) => Some(Duration(ms)),
+            None => None,
+        }
+    }
+
+    pub const fn from_days(days: u64) -> Option<Duration> {
+        match days.checked_mul(MS_PER_DAY) {
+            Some(ms) => Some(Duration(ms)),
+            None => None,
+        }
+    }
+
+    pub fn as_millis(&self) -> u64 {
+        self.0
+    }
+
+    /// Returns number of whole seconds.
+    pub fn as_secs(&self) -> u64 {
+        self.0 / MS_PER_SECOND
+    }
+
+    pub fn checked_add(&self, other: Duration) -> Option<Duration> {
+        self.0.checked_add(other.0).map(Duration)
+    }
+
+    pub fn checked_sub(&self, other: Duration) -> Option<Duration> {
+        self.0.checked_sub(other.0).map(Duration)
+    }
+}
+
+/// Day of week.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    /// Returns number of days since Monday, 0 to 6.
+    pub fn days_from_monday(&self) -> u8 {
+        *self as u8
+    }
+
+    fn from_days_since_epoch(days: u64) -> Weekday {
+        // 1970-01-01 was Thursday.
+        match (days + 3) % 7 {
+            0 => Weekday::Monday,
+            1 => Weekday::Tuesday,
+            2 => Weekday::Wednesday,
+            3 => Weekday::Thursday,
+            4 => Weekday::Friday,
+            5 => Weekday::Saturday,
+            _ => Weekday::Sunday,
+        }
+    }
+}
+
+/// Point in time, milliseconds since the Unix epoch, e.g. block's mean time or
+/// transaction timestamp.
+///
+/// # Examples
+///
+/// ```
+/// # use power::*;
+/// let deadline: Timestamp = "2024-03-01T12:00:00Z".parse().unwrap();
+/// let leap_day: Timestamp = "2024-02-29".parse().unwrap();
+/// assert!(leap_day < deadline);
+/// let next_day = leap_day.checked_add(Duration::from_days(1).unwrap());
+/// assert_eq!(Some(deadline.start_of_day()), next_day);
+/// assert_eq!(Some(deadline.start_of_day()), leap_day.start_of_next_month());
+///
+/// // Offset is subtracted to get UTC, fraction is kept up to milliseconds.
+/// let t: Timestamp = "2024-03-01T14:30:00.1259+02:30".parse().unwrap();
+/// assert_eq!("2024-03-01T12:00:00.125Z", t.to_string());
+/// let t: Timestamp = "2024-02-29T21:00:00-03:00".parse().unwrap();
+/// assert_eq!(deadline.start_of_day(), t);
+///
+/// assert_eq!(Err(TimeError::Invalid), "2023-02-29".parse::<Timestamp>());
+/// assert_eq!(Err(TimeError::Invalid), "2024-04-31".parse::<Timestamp>());
+/// assert_eq!(Err(TimeError::Invalid), "2024-03-01T24:00:00Z".parse::<Timestamp>());
+/// assert_eq!(Err(TimeError::Invalid), "2024-03-01T12:00+25:00".parse::<Timestamp>());
+/// assert_eq!(Err(TimeError::OutOfRange), "1969-12-31".parse::<Timestamp>());
+///
+/// assert_eq!(None, Timestamp::from_millis(u64::MAX).start_of_next_month());
+/// assert_eq!(None, Timestamp::from_secs(u64::MAX));
+/// assert_eq!(None, Duration::from_days(u64::MAX / 1000));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Timestamp(u64);
+
+impl Timestamp {
+    pub const fn from_millis(ms: u64) -> Timestamp {
+        Timestamp(ms)
+    }
+
+    /// Returns `None` if the time doesn't fit in `u64` milliseconds.
+    pub const fn from_secs(secs: u64) -> Option<Timestamp> {
+        match secs.checked_mul(MS_PER_SECOND) {
+            Some(ms) => Some(Timestamp(ms)),
+            None => None,
+        }
+    }
+
+    /// Returns mean time of the block being executed.
+    pub fn now() -> Timestamp {
+        Timestamp(get_mean_time())
+    }
+
+    pub fn as_millis(&self) -> u64 {
+        self.0
+    }
+
+    /// Returns number of whole seconds since the Unix epoch.
+    pub fn as_secs(&self) -> u64 {
+        self.0 / MS_PER_SECOND
+    }
+
+    pub fn checked_add(&self, d: Duration) -> Option<Timestamp> {
+        self.0.checked_add(d.0).map(Timestamp)
+    }
+
+    pub fn checked_sub(&self, d: Duration) -> Option<Timestamp> {
+        self.0.checked_sub(d.0).map(Timestamp)
+    }
+
+    /// Returns time elapsed since `earlier`, or `None` if `earlier` is later than `self`.
+    pub fn duration_since(&self, earlier: Timestamp) -> Option<Duration> {
+        self.0.checked_sub(earlier.0).map(Duration)
+    }
+
+    /// Returns number of whole days since the Unix epoch.
+    fn days(&self) -> u64 {
+        self.0 / MS_PER_DAY
+    }
+
+    pub fn weekday(&self) -> Weekday {
+        Weekday::from_days_since_epoch(self.days())
+    }
+
+    /// Returns midnight UTC of the same day.
+    pub fn start_of_day(&self) -> Timestamp {
+        Timestamp(self.days() * MS_PER_DAY)
+    }
+
+    /// Returns midnight UTC of Monday of the same week, or `None` if that Monday is
+    /// before the Unix epoch (1970-01-01 was Thursday).
+    pub fn start_of_week(&self) -> Option<Timestamp> {
+        let days = self.days().checked_sub(self.weekday().days_from_monday() as u64)?;
+        Some(Timestamp(days * MS_PER_DAY))
+    }
+
+    /// Returns midnight UTC of the first day of the same month.
+    pub fn start_of_month(&self) -> Timestamp {
+        let dt = self.to_datetime();
+        Timestamp(days_from_civil(dt.year, dt.month, 1) * MS_PER_DAY)
+    }
+
+    /// Returns midnight UTC of the first day of the next month, or `None` if it doesn't
+    /// fit in `u64` milliseconds.
+    pub fn start_of_next_month(&self) -> Option<Timestamp> {
+        let dt = self.to_datetime();
+        let (year, month) = if dt.month == 12 {
+            (dt.year + 1, 1)
+        } else {
+            (dt.year, dt.month + 1)
+        };
+        days_from_civil(year, month, 1).checked_mul(MS_PER_DAY).map(Timestamp)
+    }
+
+    /// Returns true if both timestamps are within the same UTC day.
+    pub fn is_same_day(&self, other: Timestamp) -> bool {
+        self.days() == other.days()
+    }
+
+    /// Returns UTC date and time.
+    pub fn to_datetime(&self) -> DateTime {
+        let (year, month, day) = civil_from_days(self.days());
+        let ms = self.0 % MS_PER_DAY;
+        DateTime {
+            year: year,
+            month: month,
+            day: day,
+            hour: (ms / MS_PER_HOUR) as u8,
+            minute: (ms % MS_PER_HOUR / MS_PER_MINUTE) as u8,
+            second: (ms % MS_PER_MINUTE / MS_PER_SECOND) as u8,
+            millisecond: (ms % MS_PER_SECOND) as u16,
+        }
+    }
+}
+
+impl Display for Timestamp {
+    /// Formats timestamp as ISO-8601 UTC date and time.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(&self.to_datetime(), f)
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = TimeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_iso8601(s)
+    }
+}
+
+/// UTC date and time broken down into calendar fields.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DateTime {
+    pub year: u32,
+    /// 1 to 12.
+    pub month: u8,
+    /// 1 to 31.
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub millisecond: u16,
+}
+
+impl DateTime {
+    /// Creates midnight of the specified date.
+    pub fn from_date(year: u32, month: u8, day: u8) -> Result<DateTime, TimeError> {
+        DateTime::new(year, month, day, 0, 0, 0)
+    }
+
+    pub fn new(
+        year: u32,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+    ) -> Result<DateTime, TimeError> {
+        let dt = DateTime {
+            year: year,
+            month: month,
+            day: day,
+            hour: hour,
+            minute: minute,
+            second: second,
+            millisecond: 0,
+        };
+        dt.validate()?;
+        Ok(dt)
+    }
+
+    fn validate(&self) -> Result<(), TimeError> {
+        if self.month < 1 || self.month > 12 {
+            return Err(TimeError::Invalid);
+        }
+        if self.day < 1 || self.day > days_in_month(self.year, self.month) {
+            return Err(TimeError::Invalid);
+        }
+        if self.hour > 23 || self.minute > 59 || self.second > 59 || self.millisecond > 999 {
+            return Err(TimeError::Invalid);
+        }
+        if self.year < 1970 {
+            return Err(TimeError::OutOfRange);
+        }
+        Ok(())
+    }
+
+    pub fn to_timestamp(&self) -> Result<Timestamp, TimeError> {
+        self.validate()?;
+        let days = days_from_civil(self.year, self.month, self.day);
+        let ms = self.hour as u64 * MS_PER_HOUR
+            + self.minute as u64 * MS_PER_MINUTE
+            + self.second as u64 * MS_PER_SECOND
+            + self.millisecond as u64;
+        days.checked_mul(MS_PER_DAY)
+            .and_then(|d| d.checked_add(ms))
+            .map(Timestamp)
+            .ok_or(TimeError::OutOfRange)
+    }
+}
+
+impl Display for DateTime {
+    /// Formats as `YYYY-MM-DDTHH:MM:SSZ`, adding `.mmm` if milliseconds are not zero.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )?;
+        if self.millisecond != 0 {
+            write!(f, ".{:03}", self.millisecond)?;
+        }
+        f.write_str("Z")
+    }
+}
+
+impl FromStr for DateTime {
+    type Err = TimeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(parse_iso8601(s)?.to_datetime())
+    }
+}
+
+fn is_leap_year(year: u32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: u32, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Returns number of days since 1970-01-01, the date must not be earlier.
+///
+/// See http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: u32, month: u8, day: u8) -> u64 {
+    let y = if month <= 2 { year - 1 } else { year } as u64;
+    let m = month as u64;
+    let era = y / 400;
+    let yoe = y - era * 400;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as u64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Returns year, month and day for number of days since 1970-01-01.
+///
+/// See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: u64) -> (u32, u8, u8) {
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = (yoe + era * 400) as u32 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Parses fixed number of decimal digits.
+fn parse_digits(s: &[u8]) -> Result<u32, TimeError> {
+    if s.len() == 0 {
+        return Err(TimeError::Invalid);
+    }
+    let mut n: u32 = 0;
+    for b in s {
+        if !b.is_ascii_digit() {
+            return Err(TimeError::Invalid);
+        }
+        n = n * 10 + (b - b'0') as u32;
+    }
+    Ok(n)
+}
+
+/// Parses `YYYY-MM-DD`, optionally followed by `THH:MM[:SS[.fff]]` and `Z` or `±HH:MM`
+/// offset. Date without time means midnight, time without offset means UTC.
+fn parse_iso8601(s: &str) -> Result<Timestamp, TimeError> {
+    let b = s.as_bytes();
+    if b.len() < 10 || b[4] != b'-' || b[7] != b'-' {
+        return Err(TimeError::Invalid);
+    }
+    let mut dt = DateTime {
+        year: parse_digits(&b[0..4])?,
+        month: parse_digits(&b[5..7])? as u8,
+        day: parse_digits(&b[8..10])? as u8,
+        hour: 0,
+        minute: 0,
+        second: 0,
+        millisecond: 0,
+    };
+    let mut rest = &b[10..];
+    let mut offset: i64 = 0;
+    if rest.len() > 0 {
+        if rest[0] != b'T' && rest[0] != b't' && rest[0] != b' ' {
+            return Err(TimeError::Invalid);
+        }
+        rest = &rest[1..];
+        if rest.len() < 5 || rest[2] != b':' {
+            return Err(TimeError::Invalid);
+        }
+        dt.hour = parse_digits(&rest[0..2])? as u8;
+        dt.minute = parse_digits(&rest[3..5])? as u8;
+        rest = &rest[5..];
+        if rest.len() > 0 && rest[0] == b':' {
+            if rest.len() < 3 {
+                return Err(TimeError::Invalid);
+            }
+            dt.second = parse_digits(&rest[1..3])? as u8;
+            rest = &rest[3..];
+        }
+        if rest.len() > 0 && (rest[0] == b'.' || rest[0] == b',') {
+            let n = rest[1..].iter().take_while(|c| c.is_ascii_digit()).count();
+            // Digits beyond milliseconds are truncated.
+            let frac = &rest[1..1 + n.min(3)];
+            let scale = [100, 10, 1];
+            dt.millisecond = (parse_digits(frac)? * scale[frac.len() - 1]) as u16;
+            rest = &rest[1 + n..];
+        }
+        match rest {
+            b"" | b"Z" | b"z" => {}
+            [sign @ b'+', ..] | [sign @ b'-', ..] if rest.len() == 6 && rest[3] == b':' => {
+                let hours = parse_digits(&rest[1..3])?;
+                let minutes = parse_digits(&rest[4..6])?;
+                if hours > 23 || minutes > 59 {
+                    return Err(TimeError::Invalid);
+                }
+                offset = (hours as u64 * MS_PER_HOUR + minutes as u64 * MS_PER_MINUTE) as i64;
+                if *sign == b'-' {
+                    offset = -offset;
+                }
+            }
+            _ => return Err(TimeError::Invalid),
+        }
+    }
+    let local = dt.to_timestamp()?.0 as i64;
+    let utc = local - offset;
+    if utc < 0 {
+        return Err(TimeError::OutOfRange);
+    }
+    Ok(Timestamp(utc as u64))
+}
@@ -0,0 +1,392 @@
+use crate::*;
+
+/// Error returned by `emit` of transaction builders when required field is absent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TxBuildError {
+    Missing(&'static str),
+    /// Extradata key is set by the builder itself and can't be passed to `extra`.
+    ReservedKey(&'static str),
+}
+
+impl Display for TxBuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TxBuildError::Missing(field) => write!(f, "transaction field {} is required", field),
+            TxBuildError::ReservedKey(key) => write!(f, "extradata key {} is reserved", key),
+        }
+    }
+}
+
+/// Fields shared by all transaction kinds.
+#[derive(Default)]
+struct Common {
+    payload: Vec<PayloadItem>,
+    extra: Vec<(Value, Value)>,
+    not_before: Option<u64>,
+}
+
+impl Common {
+    /// Returns encoded transaction with keys sorted the way node encodes them.
+    ///
+    /// `extra` entries are added to user-provided extradata, which must not use the same
+    /// keys.
+    fn encode(
+        &self,
+        kind: TxKind,
+        mut fields: Vec<(&'static str, Value)>,
+        extra: Vec<(&'static str, Value)>,
+    ) -> Result<Value, TxBuildError> {
+        for (key, _) in &extra {
+            if self.extra.iter().any(|(k, _)| k.as_str() == Some(*key)) {
+                return Err(TxBuildError::ReservedKey(key));
+            }
+        }
+        fields.push(("k", kind.into()));
+        let p: Vec<Value> = self.payload.iter().map(Value::from).collect();
+        fields.push(("p", Value::Array(p)));
+        let mut e = self.extra.clone();
+        e.extend(extra.into_iter().map(|(k, v)| (k.into(), v)));
+        if e.len() > 0 {
+            fields.push(("e", Value::Map(e)));
+        }
+        if let Some(nb) = self.not_before {
+            fields.push(("nb", nb.into()));
+        }
+        fields.sort_by(|a, b| a.0.cmp(b.0));
+        Ok(Value::Map(fields.into_iter().map(|(k, v)| (k.into(), v)).collect()))
+    }
+}
+
+fn emit_value(tx: Value) {
+    let enc = serialize(tx);
+    unsafe {
+        power_env::emit_tx(enc.len(), enc.as_ptr());
+    }
+}
+
+macro_rules! common_builder_methods {
+    () => {
+        /// Adds payload item, e.g. transfer or fee.
+        pub fn payload(mut self, payload: PayloadItem) -> Self {
+            self.common.payload.push(payload);
+            self
+        }
+
+        /// Adds extradata entry.
+        pub fn extra<T: Into<Value>>(mut self, key: &str, value: T) -> Self {
+            self.common.extra.push((key.into(), value.into()));
+            self
+        }
+
+        /// Delays transaction until specified time.
+        pub fn not_before(mut self, not_before: u64) -> Self {
+            self.common.not_before = Some(not_before);
+            self
+        }
+
+        /// Validates and emits transaction.
+        pub fn emit(self) -> Result<(), TxBuildError> {
+            emit_value(self.to_value()?);
+            Ok(())
+        }
+    };
+}
+
+/// Builder for `TxKind::Generic` transaction: transfer and/or method call.
+pub struct GenericTx {
+    common: Common,
+    to: Address,
+    call: Option<Call>,
+    notify: Vec<NotifyEvent>,
+}
+
+impl GenericTx {
+    pub fn new(to: Address) -> Self {
+        GenericTx {
+            common: Common::default(),
+            to: to,
+            call: None,
+            notify: Vec::new(),
+        }
+    }
+
+    /// Calls method of destination contract.
+    pub fn call(mut self, method: &str, args: Vec<Value>) -> Self {
+        self.call = Some(Call {
+            method: method.to_string(),
+            args: args,
+        });
+        self
+    }
+
+    /// Adds notification to be sent when transaction is processed.
+    pub fn notify(mut self, event: NotifyEvent) -> Self {
+        self.notify.push(event);
+        self
+    }
+
+    pub fn to_value(&self) -> Result<Value, TxBuildError> {
+        let mut fields: Vec<(&'static str, Value)> = vec![("to", (&self.to).into())];
+        if let Some(ref c) = self.call {
+            fields.push(("c", c.into()));
+        }
+        if self.notify.len() > 0 {
+            fields.push(("ev", self.notify.iter().map(Value::from).collect::<Vec<_>>().into()));
+        }
+        self.common.encode(TxKind::Generic, fields, Vec::new())
+    }
+
+    common_builder_methods!();
+}
+
+/// Virtual machine running deployed contract code.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VmType {
+    Wasm,
+    Evm,
+}
+
+impl VmType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VmType::Wasm => "wasm",
+            VmType::Evm => "evm",
+        }
+    }
+}
+
+/// Builder for `TxKind::Deploy` transaction.
+///
+/// Code and VM type are put into extradata under `code` and `vm` keys, initial storage
+/// under `state` key, so these keys can't be used with `extra`.
+pub struct DeployTx {
+    common: Common,
+    code: Vec<u8>,
+    vm: VmType,
+    state: BTreeMap<Vec<u8>, Vec<u8>>,
+    call: Option<Call>,
+}
+
+impl DeployTx {
+    pub fn new<C: Into<Vec<u8>>>(code: C, vm: VmType) -> Self {
+        DeployTx {
+            common: Common::default(),
+            code: code.into(),
+            vm: vm,
+            state: BTreeMap::new(),
+            call: None,
+        }
+    }
+
+    /// Adds initial storage entry of deployed contract.
+    ///
+    /// Key and value are encoded the same way as by `write`, so the deployed contract
+    /// can get them with `read`.
+    pub fn state<T1, T2>(mut self, key: &T1, value: &T2) -> Self
+    where
+        T1: ?Sized + Serialize,
+        T2: ?Sized + Serialize,
+    {
+        self.state.insert(serialize(key), serialize(value));
+        self
+    }
+
+    /// Calls constructor method of deployed contract.
+    pub fn call(mut self, method: &str, args: Vec<Value>) -> Self {
+        self.call = Some(Call {
+            method: method.to_string(),
+            args: args,
+        });
+        self
+    }
+
+    pub fn to_value(&self) -> Result<Value, TxBuildError> {
+        if self.code.len() == 0 {
+            return Err(TxBuildError::Missing("code"));
+        }
+        let mut fields: Vec<(&'static str, Value)> = Vec::new();
+        if let Some(ref c) = self.call {
+            fields.push(("c", c.into()));
+        }
+        let mut extra = vec![("code", Value::Binary(self.code.clone()))];
+        if self.state.len() > 0 {
+            let state: Vec<(Value, Value)> = self
+                .state
+                .iter()
+                .map(|(k, v)| (Value::Binary(k.clone()), Value::Binary(v.clone())))
+                .collect();
+            extra.push(("state", Value::Map(state)));
+        }
+        extra.push(("vm", self.vm.as_str().into()));
+        self.common.encode(TxKind::Deploy, fields, extra)
+    }
+
+    common_builder_methods!();
+}
+
+/// Builder for `TxKind::Patch` transaction changing chain settings.
+pub struct PatchTx {
+    common: Common,
+    patches: Vec<Value>,
+}
+
+impl PatchTx {
+    pub fn new() -> Self {
+        PatchTx {
+            common: Common::default(),
+            patches: Vec::new(),
+        }
+    }
+
+    /// Adds settings patch, at least one is required.
+    pub fn patch<T: Into<Value>>(mut self, patch: T) -> Self {
+        self.patches.push(patch.into());
+        self
+    }
+
+    pub fn to_value(&self) -> Result<Value, TxBuildError> {
+        if self.patches.len() == 0 {
+            return Err(TxBuildError::Missing("patches"));
+        }
+        let fields = vec![("patches", Value::Array(self.patches.clone()))];
+        self.common.encode(TxKind::Patch, fields, Vec::new())
+    }
+
+    common_builder_methods!();
+}
+
+/// Builder for `TxKind::Register` transaction registering new wallet.
+pub struct RegisterTx {
+    common: Common,
+    keys: Vec<Vec<u8>>,
+}
+
+impl RegisterTx {
+    pub fn new() -> Self {
+        RegisterTx {
+            common: Common::default(),
+            keys: Vec::new(),
+        }
+    }
+
+    /// Adds public key of new wallet, at least one is required.
+    pub fn key<T: Into<Vec<u8>>>(mut self, key: T) -> Self {
+        self.keys.push(key.into());
+        self
+    }
+
+    pub fn to_value(&self) -> Result<Value, TxBuildError> {
+        if self.keys.len() == 0 {
+            return Err(TxBuildError::Missing("keys"));
+        }
+        let keys: Vec<Value> = self.keys.iter().map(|k| Value::Binary(k.clone())).collect();
+        self.common.encode(TxKind::Register, vec![("keys", Value::Array(keys))], Vec::new())
+    }
+
+    common_builder_methods!();
+}
+
+/// Builder for `TxKind::TStore` transaction storing extradata in the chain.
+pub struct TStoreTx {
+    common: Common,
+}
+
+impl TStoreTx {
+    pub fn new() -> Self {
+        TStoreTx {
+            common: Common::default(),
+        }
+    }
+
+    /// Stored data is the extradata, so at least one `extra` entry is required.
+    pub fn to_value(&self) -> Result<Value, TxBuildError> {
+        if self.common.extra.len() == 0 {
+            return Err(TxBuildError::Missing("extradata"));
+        }
+        self.common.encode(TxKind::TStore, Vec::new(), Vec::new())
+    }
+
+    common_builder_methods!();
+}
+
+/// Builder for `TxKind::LStore` transaction patching sender's ledger storage.
+pub struct LStoreTx {
+    common: Common,
+    patches: Vec<Value>,
+}
+
+impl LStoreTx {
+    pub fn new() -> Self {
+        LStoreTx {
+            common: Common::default(),
+            patches: Vec::new(),
+        }
+    }
+
+    /// Adds storage patch, at least one is required.
+    pub fn patch<T: Into<Value>>(mut self, patch: T) -> Self {
+        self.patches.push(patch.into());
+        self
+    }
+
+    pub fn to_value(&self) -> Result<Value, TxBuildError> {
+        if self.patches.len() == 0 {
+            return Err(TxBuildError::Missing("patches"));
+        }
+        let fields = vec![("pa", Value::Array(self.patches.clone()))];
+        self.common.encode(TxKind::LStore, fields, Vec::new())
+    }
+
+    common_builder_methods!();
+}
+
+/// Builder for `TxKind::Notify` transaction sending notifications to external URLs.
+pub struct NotifyTx {
+    common: Common,
+    notify: Vec<NotifyEvent>,
+}
+
+impl NotifyTx {
+    pub fn new() -> Self {
+        NotifyTx {
+            common: Common::default(),
+            notify: Vec::new(),
+        }
+    }
+
+    /// Adds notification, at least one is required.
+    pub fn notify(mut self, event: NotifyEvent) -> Self {
+        self.notify.push(event);
+        self
+    }
+
+    pub fn to_value(&self) -> Result<Value, TxBuildError> {
+        if self.notify.len() == 0 {
+            return Err(TxBuildError::Missing("notify"));
+        }
+        let ev: Vec<Value> = self.notify.iter().map(Value::from).collect();
+        self.common.encode(TxKind::Notify, vec![("ev", Value::Array(ev))], Vec::new())
+    }
+
+    common_builder_methods!();
+}
+
+impl NotifyEvent {
+    /// Creates notification with JSON body.
+    pub fn json(url: &str, data: &str) -> Self {
+        NotifyEvent {
+            url: url.to_string(),
+            content_type: "application/json".to_string(),
+            data: data.into(),
+        }
+    }
+
+    /// Creates notification with binary body.
+    pub fn binary<T: Into<Vec<u8>>>(url: &str, data: T) -> Self {
+        NotifyEvent {
+            url: url.to_string(),
+            content_type: "application/octet-stream".to_string(),
+            data: Value::Binary(data.into()),
+        }
+    }
+}
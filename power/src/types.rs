@@ -1,11 +1,251 @@
 use crate::*;
 
-use crc::crc32;
+use core::convert::TryFrom;
 
-use fixed_hash::construct_fixed_hash;
+/// Wallet or contract address.
+///
+/// Formatted with `Display` and parsed with `FromStr` in the textual form, e.g.
+/// `AA100000001677722185`, see `address_to_string` and `Address::parse`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Address(pub [u8; 8]);
 
-construct_fixed_hash! {
-    pub struct Address(8);
+impl Address {
+    /// Creates address from slice.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the slice length is not 8.
+    pub fn from_slice(src: &[u8]) -> Self {
+        let mut a = Address::zero();
+        a.assign_from_slice(src);
+        a
+    }
+
+    /// Copies bytes from slice into the address.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the slice length is not 8.
+    pub fn assign_from_slice(&mut self, src: &[u8]) {
+        self.0.copy_from_slice(src);
+    }
+
+    pub fn zero() -> Self {
+        Address([0; 8])
+    }
+
+    /// Returns address with all bytes set to `byte`.
+    pub fn repeat_byte(byte: u8) -> Self {
+        Address([byte; 8])
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == [0; 8]
+    }
+
+    /// Returns true if all bits set in `b` are also set in `self`.
+    pub fn covers(&self, b: &Self) -> bool {
+        &(b & self) == b
+    }
+
+    pub fn len_bytes() -> usize {
+        8
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+
+    pub fn as_fixed_bytes(&self) -> &[u8; 8] {
+        &self.0
+    }
+
+    pub fn as_fixed_bytes_mut(&mut self) -> &mut [u8; 8] {
+        &mut self.0
+    }
+
+    pub fn to_fixed_bytes(self) -> [u8; 8] {
+        self.0
+    }
+
+    pub fn as_ptr(&self) -> *const u8 {
+        self.0.as_ptr()
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.0.as_mut_ptr()
+    }
+
+    /// Creates address from big-endian bytes of `val`.
+    pub fn from_low_u64_be(val: u64) -> Self {
+        Address(val.to_be_bytes())
+    }
+
+    /// Creates address from little-endian bytes of `val`.
+    pub fn from_low_u64_le(val: u64) -> Self {
+        Address(val.to_le_bytes())
+    }
+
+    /// Creates address from native-endian bytes of `val`.
+    pub fn from_low_u64_ne(val: u64) -> Self {
+        Address(val.to_ne_bytes())
+    }
+
+    /// Returns address bytes interpreted as big-endian number.
+    pub fn to_low_u64_be(&self) -> u64 {
+        u64::from_be_bytes(self.0)
+    }
+
+    /// Returns address bytes interpreted as little-endian number.
+    pub fn to_low_u64_le(&self) -> u64 {
+        u64::from_le_bytes(self.0)
+    }
+
+    /// Returns address bytes interpreted as native-endian number.
+    pub fn to_low_u64_ne(&self) -> u64 {
+        u64::from_ne_bytes(self.0)
+    }
+}
+
+impl From<[u8; 8]> for Address {
+    fn from(bytes: [u8; 8]) -> Self {
+        Address(bytes)
+    }
+}
+
+impl From<&[u8; 8]> for Address {
+    fn from(bytes: &[u8; 8]) -> Self {
+        Address(*bytes)
+    }
+}
+
+impl From<&mut [u8; 8]> for Address {
+    fn from(bytes: &mut [u8; 8]) -> Self {
+        Address(*bytes)
+    }
+}
+
+impl From<Address> for [u8; 8] {
+    fn from(a: Address) -> Self {
+        a.0
+    }
+}
+
+impl AsRef<[u8]> for Address {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsMut<[u8]> for Address {
+    fn as_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
+impl<I: core::slice::SliceIndex<[u8]>> Index<I> for Address {
+    type Output = I::Output;
+
+    fn index(&self, index: I) -> &I::Output {
+        &self.0[index]
+    }
+}
+
+impl<I: core::slice::SliceIndex<[u8]>> core::ops::IndexMut<I> for Address {
+    fn index_mut(&mut self, index: I) -> &mut I::Output {
+        &mut self.0[index]
+    }
+}
+
+macro_rules! impl_bit_ops_for_address {
+    ($op:ident, $op_fn:ident, $op_assign:ident, $op_assign_fn:ident, $assign_tok:tt) => {
+        impl<'r> core::ops::$op_assign<&'r Address> for Address {
+            fn $op_assign_fn(&mut self, rhs: &'r Address) {
+                for (l, r) in self.0.iter_mut().zip(rhs.0.iter()) {
+                    *l $assign_tok r;
+                }
+            }
+        }
+
+        impl core::ops::$op_assign<Address> for Address {
+            fn $op_assign_fn(&mut self, rhs: Address) {
+                *self $assign_tok &rhs;
+            }
+        }
+
+        impl<'l, 'r> core::ops::$op<&'r Address> for &'l Address {
+            type Output = Address;
+
+            fn $op_fn(self, rhs: &'r Address) -> Address {
+                let mut a = *self;
+                a $assign_tok rhs;
+                a
+            }
+        }
+
+        impl core::ops::$op<Address> for Address {
+            type Output = Address;
+
+            fn $op_fn(mut self, rhs: Address) -> Address {
+                self $assign_tok &rhs;
+                self
+            }
+        }
+    };
+}
+
+impl_bit_ops_for_address!(BitOr, bitor, BitOrAssign, bitor_assign, |=);
+impl_bit_ops_for_address!(BitAnd, bitand, BitAndAssign, bitand_assign, &=);
+impl_bit_ops_for_address!(BitXor, bitxor, BitXorAssign, bitxor_assign, ^=);
+
+impl fmt::LowerHex for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        for b in &self.0 {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::UpperHex for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0X")?;
+        }
+        for b in &self.0 {
+            write!(f, "{:02X}", b)?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for Address {
+    /// Formats address as hex bytes.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:#x}", self)
+    }
+}
+
+impl Display for Address {
+    /// Formats address in textual form, see `address_to_string`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&address_to_string(self))
+    }
+}
+
+impl FromStr for Address {
+    type Err = AddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Address::parse(s)
+    }
 }
 
 impl From<Address> for Value {
@@ -23,76 +263,124 @@ impl From<&Address> for Value {
 impl FromValue for Address {
     fn from_value(arg: &Value) -> Option<Self> {
         if let Value::Binary(vec) = arg {
+            if vec.len() != 8 {
+                return None;
+            }
             return Some(Address::from_slice(&vec[..]));
         }
         if let Value::Array(vec) = arg {
             if vec.len() != 8 {
                 return None;
             }
-            let mut v: Vec<u8> = Vec::with_capacity(vec.len());
-            for b in vec {
-                v.push(b.as_u64().unwrap() as u8);
+            let mut v = [0u8; 8];
+            for (i, b) in vec.iter().enumerate() {
+                match b.as_u64() {
+                    Some(b) if b <= 0xff => v[i] = b as u8,
+                    _ => return None,
+                }
             }
-            return Some(Address::from_slice(&v[..]));
+            return Some(Address(v));
         }
         None
     }
 }
 
-pub fn string_to_address(address: &str) -> Option<Address> {
-    if address.len() == 20 {
-        let g1 = ((address.as_bytes()[0] as u8) - ('A' as u8)) as u64;
-        let g2 = ((address.as_bytes()[1] as u8) - ('A' as u8)) as u64;
-        let gr = u64::from_str_radix(&address[2..4], 10).unwrap();
-        let i = u64::from_str_radix(&address[4..18], 10).unwrap();
-        let c = u8::from_str_radix(&address[18..20], 10).unwrap();
-
-        let group = ((((g1 * 26 + g2) * 100) as u64) + gr) << 5;
-        let block = (i >> 24) as u64;
-        let wallet = (i & 0xffffff) as u64;
-
-        let v = vec![
-            ((group >> 16) & 0x1f | 0b10000000) as u8,
-            ((group >> 8) & 0xff) as u8,
-            ((group >> 0) & 0xff | ((block >> 16) & 0x1f)) as u8,
-            ((block >> 8) & 0xff) as u8,
-            ((block >> 0) & 0xff) as u8,
-            ((wallet >> 16) & 0xff) as u8,
-            ((wallet >> 8) & 0xff) as u8,
-            ((wallet >> 0) & 0xff) as u8,
-        ];
-
-        let cc = (crc32::checksum_ieee(&v[..]) % 100) as u8;
-        if c == cc {
-            Some(Address::from_slice(&v[..]))
+/// Error returned by `Address::parse`.
+pub use power_address::AddressError;
+
+impl Address {
+    /// Parses textual address as returned by `address_to_string`.
+    ///
+    /// Lowercase letters are accepted, spaces, `-` and `_` are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use power::*;
+    /// let a = Address::parse("AA100000001677722185").unwrap();
+    /// assert_eq!(Ok(a), Address::parse("aa10-0000-0016-7772-2185"));
+    /// ```
+    pub fn parse(address: &str) -> Result<Address, AddressError> {
+        power_address::parse(address).map(Address)
+    }
+
+    /// Creates public address, see `AddressInfo` for meaning of the parts.
+    ///
+    /// Returns `None` if block doesn't fit in 21 bits or wallet in 24 bits.
+    pub fn from_parts(group: u16, block: u64, wallet: u32) -> Option<Address> {
+        power_address::pack(group, block, wallet).map(Address)
+    }
+
+    /// Returns true for public address, i.e. allocated in a chain and having a group.
+    pub fn is_public(&self) -> bool {
+        self.0[0] & 0x20 == 0
+    }
+
+    /// Returns group of public address, `None` for private address.
+    pub fn group(&self) -> Option<u16> {
+        if !self.is_public() {
+            return None;
+        }
+        let a = &self.0;
+        Some((((a[2] as u64) | (a[1] as u64) << 8 | (0x1f & a[0] as u64) << 16) >> 5) as u16)
+    }
+
+    /// Returns block number, 21 bits for public address and 37 bits for private one.
+    pub fn block(&self) -> u64 {
+        let a = &self.0;
+        let low = (a[4] as u64) | (a[3] as u64) << 8;
+        if self.is_public() {
+            low | (0x1f & a[2] as u64) << 16
         } else {
-            None
+            low | (a[2] as u64) << 16 | (a[1] as u64) << 24 | (0x1f & a[0] as u64) << 32
         }
-    } else if address.len() == 18 {
-        let a = u64::from_str_radix(&address[0..16], 16).unwrap();
-        let c = u8::from_str_radix(&address[16..18], 16).unwrap();
-        let v = vec![
-            ((a >> 8 * 7) & 0xff | 0b10100000) as u8,
-            ((a >> 8 * 6) & 0xff) as u8,
-            ((a >> 8 * 5) & 0xff) as u8,
-            ((a >> 8 * 4) & 0xff) as u8,
-            ((a >> 8 * 3) & 0xff) as u8,
-            ((a >> 8 * 2) & 0xff) as u8,
-            ((a >> 8 * 1) & 0xff) as u8,
-            ((a >> 8 * 0) & 0xff) as u8,
-        ];
-
-        let cc = (crc32::checksum_ieee(&v[..]) & 0xff) as u8;
-        if c == cc {
-            Some(Address::from_slice(&v[..]))
+    }
+
+    /// Returns number of chain (shard) the public address was allocated in.
+    ///
+    /// The chain number is stored in the block part of the address.
+    pub fn chain(&self) -> Option<u64> {
+        if self.is_public() {
+            Some(self.block())
         } else {
             None
         }
-    } else {
-        None
+    }
+
+    /// Returns wallet number within the block, 24 bits.
+    pub fn wallet(&self) -> u32 {
+        (self.0[7] as u32) | (self.0[6] as u32) << 8 | (self.0[5] as u32) << 16
+    }
+
+    /// Returns all parts of the address.
+    pub fn info(&self) -> AddressInfo {
+        AddressInfo {
+            public: self.is_public(),
+            group: self.group(),
+            block: self.block(),
+            wallet: self.wallet(),
+        }
     }
 }
 
+/// Parts of address, see `Address::info`.
+///
+/// Public address is `0b100` followed by 16-bit group, 21-bit block and 24-bit wallet,
+/// private address is `0b101` followed by 37-bit block and 24-bit wallet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AddressInfo {
+    pub public: bool,
+    /// `None` for private address.
+    pub group: Option<u16>,
+    pub block: u64,
+    pub wallet: u32,
+}
+
+/// Parses textual address, see `Address::parse`.
+pub fn string_to_address(address: &str) -> Option<Address> {
+    Address::parse(address).ok()
+}
+
 fn pad(base: u64, num: u64, size: u64) -> String {
     let alnum: &[u8] = &"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ".as_bytes();
     let b26: &[u8] = &"ABCDEFGHIJKLMNOPQRSTUVWXYZ".as_bytes();
@@ -113,35 +401,22 @@ fn pad(base: u64, num: u64, size: u64) -> String {
 }
 
 pub fn address_to_string(address: &Address) -> String {
-    let wallet =
-        ((address[7] as u64) | ((address[6] as u64) << 8) | ((address[5] as u64) << 16)) as u64;
-    let checksum = crc32::checksum_ieee(address.as_bytes()) as u64;
-    if address[0] & 0x20 > 0 {
-        let block = ((((address[4] as u64)
-            | (address[3] as u64) << 8
-            | (address[2] as u64) << 16
-            | (address[1] as u64) << 24)
-            >> 0)
-            + ((0x1f as u64 & (address[0] as u64)) * (4294967296 as u64)))
-            as u64;
-        return pad(16, block, 10) + &pad(16, wallet, 6) + &pad(16, checksum % 256, 2);
-    } else {
-        let block = ((address[4] as u64)
-            | ((address[3] as u64) << 8)
-            | ((0x1f & (address[2] as u64)) << 16)) as u64;
-        let group = (((address[2] as u64)
-            | ((address[1] as u64) << 8)
-            | ((0x1f & (address[0] as u64)) << 16))
-            >> 5) as u64;
-        let int_part = (wallet + (block * 16777216)) as u64;
-        return pad(26, group / 100, 2)
-            + &pad(10, group % 100, 2)
-            + &pad(10, int_part, 14)
-            + &pad(10, checksum % 100, 2);
-    }
-}
-
-#[derive(Clone, Copy, Debug)]
+    let wallet = address.wallet() as u64;
+    let checksum = power_address::checksum(address.as_fixed_bytes()) as u64;
+    match address.group() {
+        None => pad(16, address.block(), 10) + &pad(16, wallet, 6) + &pad(16, checksum % 256, 2),
+        Some(group) => {
+            let group = group as u64;
+            let int_part = wallet + (address.block() << 24);
+            pad(26, group / 100, 2)
+                + &pad(10, group % 100, 2)
+                + &pad(10, int_part, 14)
+                + &pad(10, checksum % 100, 2)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum TxKind {
     Generic = 16,
     Register,
@@ -158,27 +433,31 @@ impl From<TxKind> for Value {
         Value::Integer((arg as u64).into())
     }
 }
+impl TryFrom<&Value> for TxKind {
+    type Error = TxDecodeError;
+
+    fn try_from(arg: &Value) -> Result<Self, TxDecodeError> {
+        match arg.as_u64() {
+            Some(16) => Ok(TxKind::Generic),
+            Some(17) => Ok(TxKind::Register),
+            Some(18) => Ok(TxKind::Deploy),
+            Some(19) => Ok(TxKind::Patch),
+            Some(20) => Ok(TxKind::Block),
+            Some(21) => Ok(TxKind::TStore),
+            Some(22) => Ok(TxKind::LStore),
+            Some(23) => Ok(TxKind::Notify),
+            _ => Err(TxDecodeError::Malformed("kind")),
+        }
+    }
+}
+
 impl FromValue for TxKind {
     fn from_value(arg: &Value) -> Option<Self> {
-        if arg.is_u64() {
-            match arg.as_u64().unwrap() {
-                16 => Some(TxKind::Generic),
-                17 => Some(TxKind::Register),
-                18 => Some(TxKind::Deploy),
-                19 => Some(TxKind::Patch),
-                20 => Some(TxKind::Block),
-                21 => Some(TxKind::TStore),
-                22 => Some(TxKind::LStore),
-                23 => Some(TxKind::Notify),
-                _ => None,
-            }
-        } else {
-            None
-        }
+        Self::try_from(arg).ok()
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Purpose {
     Transfer = 0,
     SrcFee,
@@ -192,37 +471,325 @@ impl From<Purpose> for Value {
     }
 }
 
+impl TryFrom<&Value> for Purpose {
+    type Error = TxDecodeError;
+
+    fn try_from(arg: &Value) -> Result<Self, TxDecodeError> {
+        match arg.as_u64() {
+            Some(0) => Ok(Purpose::Transfer),
+            Some(1) => Ok(Purpose::SrcFee),
+            Some(2) => Ok(Purpose::DstFee),
+            Some(3) => Ok(Purpose::Gas),
+            _ => Err(TxDecodeError::Malformed("payload.purpose")),
+        }
+    }
+}
+
 impl FromValue for Purpose {
     fn from_value(arg: &Value) -> Option<Self> {
-        if arg.is_u64() {
-            match arg.as_u64().unwrap() {
-                0 => Some(Purpose::Transfer),
-                1 => Some(Purpose::SrcFee),
-                2 => Some(Purpose::DstFee),
-                3 => Some(Purpose::Gas),
-                _ => None,
+        Self::try_from(arg).ok()
+    }
+}
+
+/// Maximum length of currency ticker.
+pub const MAX_TICKER_LEN: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CurrencyError {
+    Empty,
+    TooLong,
+    /// Ticker contains byte other than uppercase ASCII letter or digit.
+    BadCharacter(u8),
+}
+
+impl Display for CurrencyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CurrencyError::Empty => f.write_str("currency ticker is empty"),
+            CurrencyError::TooLong => {
+                write!(f, "currency ticker is longer than {}", MAX_TICKER_LEN)
             }
-        } else {
-            None
+            CurrencyError::BadCharacter(c) => write!(f, "currency ticker contains byte {:#04x}", c),
         }
     }
 }
 
-#[derive(Debug)]
+/// Currency ticker, e.g. `SK`.
+///
+/// Tickers created by `new` or parsed from string consist of 1 to `MAX_TICKER_LEN`
+/// uppercase ASCII letters and digits. Decoding doesn't validate, so tickers which came
+/// from the node are kept as is.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Currency(Vec<u8>);
+
+impl Currency {
+    pub fn new(ticker: &[u8]) -> Result<Currency, CurrencyError> {
+        if ticker.len() == 0 {
+            return Err(CurrencyError::Empty);
+        }
+        if ticker.len() > MAX_TICKER_LEN {
+            return Err(CurrencyError::TooLong);
+        }
+        for &c in ticker {
+            if !(c.is_ascii_uppercase() || c.is_ascii_digit()) {
+                return Err(CurrencyError::BadCharacter(c));
+            }
+        }
+        Ok(Currency(ticker.to_vec()))
+    }
+
+    /// Creates ticker from arbitrary bytes without validation.
+    pub fn from_bytes(ticker: &[u8]) -> Currency {
+        Currency(ticker.to_vec())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0[..]
+    }
+
+    /// Returns ticker as string, or empty string if it isn't valid UTF-8.
+    pub fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.0[..]).unwrap_or("")
+    }
+}
+
+impl AsRef<[u8]> for Currency {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl core::borrow::Borrow<[u8]> for Currency {
+    fn borrow(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl FromStr for Currency {
+    type Err = CurrencyError;
+
+    fn from_str(s: &str) -> Result<Currency, CurrencyError> {
+        Currency::new(s.as_bytes())
+    }
+}
+
+impl Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match core::str::from_utf8(&self.0[..]) {
+            Ok(s) => f.write_str(s),
+            Err(_) => {
+                f.write_str("0x")?;
+                for b in &self.0 {
+                    write!(f, "{:02x}", b)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Debug for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Currency({})", self)
+    }
+}
+
+impl From<Currency> for Value {
+    fn from(arg: Currency) -> Self {
+        Value::Binary(arg.0)
+    }
+}
+
+impl From<&Currency> for Value {
+    fn from(arg: &Currency) -> Self {
+        Value::Binary(arg.0.clone())
+    }
+}
+
+impl FromValue for Currency {
+    /// Accepts ticker encoded either as binary or as string, doesn't validate it.
+    fn from_value(arg: &Value) -> Option<Self> {
+        Some(Currency::from_bytes(arg.as_slice()?))
+    }
+}
+
+#[derive(Debug, PartialEq)]
 pub struct Amount {
-    pub currency: Vec<u8>,
+    pub currency: Currency,
     pub amount: u64,
 }
 
-#[derive(Debug)]
+impl From<Amount> for Value {
+    fn from(arg: Amount) -> Self {
+        (arg.currency, arg.amount).into()
+    }
+}
+
+impl From<&Amount> for Value {
+    fn from(arg: &Amount) -> Self {
+        (&arg.currency, arg.amount).into()
+    }
+}
+
+impl TryFrom<&Value> for Amount {
+    type Error = TxDecodeError;
+
+    fn try_from(arg: &Value) -> Result<Self, TxDecodeError> {
+        let currency: Option<Currency> = from_value(&arg[0]);
+        match (arg.as_array().map(|a| a.len()), currency, &arg[1]) {
+            (Some(2), Some(c), a) if a.is_u64() => Ok(Amount {
+                currency: c,
+                amount: a.as_u64().unwrap_or(0),
+            }),
+            _ => Err(TxDecodeError::Malformed("amount")),
+        }
+    }
+}
+
+impl FromValue for Amount {
+    fn from_value(arg: &Value) -> Option<Self> {
+        Self::try_from(arg).ok()
+    }
+}
+
+/// Amounts of several currencies, e.g. contract's balance.
+///
+/// Currencies with zero amount are not stored.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Balances {
+    amounts: BTreeMap<Currency, u64>,
+}
+
+impl Balances {
+    pub fn new() -> Balances {
+        Balances {
+            amounts: BTreeMap::new(),
+        }
+    }
+
+    /// Returns amount of the currency, 0 if there is none.
+    pub fn get<C: AsRef<[u8]>>(&self, currency: C) -> u64 {
+        self.amounts.get(currency.as_ref()).cloned().unwrap_or(0)
+    }
+
+    pub fn has_at_least<C: AsRef<[u8]>>(&self, currency: C, amount: u64) -> bool {
+        self.get(currency) >= amount
+    }
+
+    /// Returns number of currencies with non-zero amount.
+    pub fn len(&self) -> usize {
+        self.amounts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.amounts.is_empty()
+    }
+
+    /// Returns iterator over currencies and their amounts, ordered by currency.
+    pub fn iter(&self) -> impl Iterator<Item = (&Currency, u64)> {
+        self.amounts.iter().map(|(c, a)| (c, *a))
+    }
+
+    fn set(&mut self, currency: &Currency, amount: u64) {
+        if amount == 0 {
+            self.amounts.remove(currency);
+        } else {
+            self.amounts.insert(currency.clone(), amount);
+        }
+    }
+
+    /// Adds amount of the currency and returns new amount.
+    ///
+    /// Returns `None` and leaves balances unchanged on overflow.
+    pub fn checked_add(&mut self, currency: &Currency, amount: u64) -> Option<u64> {
+        let res = self.get(currency).checked_add(amount)?;
+        self.set(currency, res);
+        Some(res)
+    }
+
+    /// Subtracts amount of the currency and returns new amount.
+    ///
+    /// Returns `None` and leaves balances unchanged if there is not enough.
+    pub fn checked_sub(&mut self, currency: &Currency, amount: u64) -> Option<u64> {
+        let res = self.get(currency).checked_sub(amount)?;
+        self.set(currency, res);
+        Some(res)
+    }
+
+    /// Adds amount of the currency, stopping at `u64::MAX` instead of overflowing.
+    pub fn saturating_add(&mut self, currency: &Currency, amount: u64) -> u64 {
+        let res = self.get(currency).saturating_add(amount);
+        self.set(currency, res);
+        res
+    }
+
+    /// Returns sum of two balances, or `None` on overflow.
+    pub fn checked_add_all(&self, other: &Balances) -> Option<Balances> {
+        let mut res = self.clone();
+        for (c, a) in other.iter() {
+            res.checked_add(c, a)?;
+        }
+        Some(res)
+    }
+
+    /// Returns difference of two balances, or `None` if any currency isn't enough.
+    pub fn checked_sub_all(&self, other: &Balances) -> Option<Balances> {
+        let mut res = self.clone();
+        for (c, a) in other.iter() {
+            res.checked_sub(c, a)?;
+        }
+        Some(res)
+    }
+
+    pub fn to_amounts(&self) -> Vec<Amount> {
+        self.iter()
+            .map(|(c, a)| Amount {
+                currency: c.clone(),
+                amount: a,
+            })
+            .collect()
+    }
+}
+
+impl From<&Balances> for Value {
+    fn from(arg: &Balances) -> Self {
+        Value::Map(
+            arg.iter()
+                .map(|(c, a)| (c.into(), a.into()))
+                .collect(),
+        )
+    }
+}
+
+impl From<Balances> for Value {
+    fn from(arg: Balances) -> Self {
+        Value::from(&arg)
+    }
+}
+
+impl FromValue for Balances {
+    /// Decodes map of currency to amount, returns `None` if any entry is malformed.
+    fn from_value(arg: &Value) -> Option<Self> {
+        let mut res = Balances::new();
+        for (k, v) in arg.as_map()? {
+            if !v.is_u64() {
+                return None;
+            }
+            res.checked_add(&from_value(k)?, v.as_u64()?)?;
+        }
+        Some(res)
+    }
+}
+
+#[derive(Debug, PartialEq)]
 pub struct PayloadItem {
     pub purpose: Purpose,
     pub amount: Amount,
 }
 
 impl PayloadItem {
-    pub fn new<C: Into<Vec<u8>>>(p: Purpose, curr: C, amount: u64) -> Self {
-        PayloadItem {purpose: p, amount: Amount { currency: curr.into(), amount: amount}}
+    pub fn new(p: Purpose, curr: Currency, amount: u64) -> Self {
+        PayloadItem {purpose: p, amount: Amount { currency: curr, amount: amount}}
     }
 }
 
@@ -230,7 +797,7 @@ impl From<PayloadItem> for Value {
     fn from(arg: PayloadItem) -> Self {
         let mut a = Vec::new();
         a.push(arg.purpose.into());
-        a.push(Value::Binary(arg.amount.currency));
+        a.push(arg.amount.currency.into());
         a.push(arg.amount.amount.into());
         Value::Array(a)
     }
@@ -239,85 +806,543 @@ impl From<&PayloadItem> for Value {
     fn from(arg: &PayloadItem) -> Self {
         let mut a = Vec::new();
         a.push(arg.purpose.into());
-        a.push(Value::Binary(arg.amount.currency.clone()));
+        a.push((&arg.amount.currency).into());
         a.push(arg.amount.amount.into());
         Value::Array(a)
     }
 }
 
-impl FromValue for PayloadItem {
-    fn from_value(arg: &Value) -> Option<Self> {
-        let p: Purpose = from_value(&arg[0]).unwrap();
-        let c1: Option<Vec<u8>> = from_value(&arg[1]);
-        let c2: Option<String> = from_value(&arg[1]);
-        let c: Vec<u8>;
-        if c1.is_some() {
-            c = c1.unwrap();
-        } else if c2.is_some() {
-            c = c2.unwrap().as_bytes().to_vec();
-        } else {
-            return None;
-        }
-        let a: u64 = from_value(&arg[2]).unwrap();
-        Some(PayloadItem {
+impl TryFrom<&Value> for PayloadItem {
+    type Error = TxDecodeError;
+
+    fn try_from(arg: &Value) -> Result<Self, TxDecodeError> {
+        let a = match arg.as_array() {
+            Some(a) if a.len() == 3 => a,
+            _ => return Err(TxDecodeError::Malformed("payload")),
+        };
+        let p = Purpose::try_from(&a[0])?;
+        // Currency may come either as binary or as string.
+        let c: Currency = match from_value(&a[1]) {
+            Some(c) => c,
+            None => return Err(TxDecodeError::Malformed("payload.currency")),
+        };
+        let amount = match a[2].as_u64() {
+            Some(x) if a[2].is_u64() => x,
+            _ => return Err(TxDecodeError::Malformed("payload.amount")),
+        };
+        Ok(PayloadItem {
             purpose: p,
             amount: Amount {
                 currency: c,
-                amount: a,
+                amount: amount,
             },
         })
     }
 }
 
+impl FromValue for PayloadItem {
+    fn from_value(arg: &Value) -> Option<Self> {
+        Self::try_from(arg).ok()
+    }
+}
+
+/// Method call requested by transaction.
+#[derive(Debug, PartialEq)]
+pub struct Call {
+    pub method: String,
+    pub args: Vec<Value>,
+}
+
+impl From<Call> for Value {
+    fn from(arg: Call) -> Self {
+        (arg.method, arg.args).into()
+    }
+}
+
+impl From<&Call> for Value {
+    fn from(arg: &Call) -> Self {
+        (arg.method.as_str(), arg.args.clone()).into()
+    }
+}
+
+impl TryFrom<&Value> for Call {
+    type Error = TxDecodeError;
+
+    fn try_from(arg: &Value) -> Result<Self, TxDecodeError> {
+        let method = match arg[0].as_str() {
+            Some(m) => m.to_string(),
+            None => return Err(TxDecodeError::Malformed("call.method")),
+        };
+        let args = match &arg[1] {
+            Value::Array(a) => a.clone(),
+            Value::Nil => vec![],
+            _ => return Err(TxDecodeError::Malformed("call.args")),
+        };
+        Ok(Call {
+            method: method,
+            args: args,
+        })
+    }
+}
+
+impl FromValue for Call {
+    fn from_value(arg: &Value) -> Option<Self> {
+        Self::try_from(arg).ok()
+    }
+}
+
+/// Notification sent by node to external URL when transaction is processed.
+#[derive(Debug, PartialEq)]
+pub struct NotifyEvent {
+    pub url: String,
+    pub content_type: String,
+    pub data: Value,
+}
+
+impl From<NotifyEvent> for Value {
+    fn from(arg: NotifyEvent) -> Self {
+        Value::from(&arg)
+    }
+}
+
+impl From<&NotifyEvent> for Value {
+    fn from(arg: &NotifyEvent) -> Self {
+        // Keys are sorted the way node encodes them, "ct" is omitted when unknown.
+        let mut m: Vec<(Value, Value)> = Vec::with_capacity(3);
+        if arg.content_type.len() > 0 {
+            m.push(("ct".into(), arg.content_type.as_str().into()));
+        }
+        m.push(("d".into(), arg.data.clone()));
+        m.push(("u".into(), arg.url.as_str().into()));
+        Value::Map(m)
+    }
+}
+
+impl TryFrom<&Value> for NotifyEvent {
+    type Error = TxDecodeError;
+
+    fn try_from(arg: &Value) -> Result<Self, TxDecodeError> {
+        let m = map_fields(arg).ok_or(TxDecodeError::Malformed("notify"))?;
+        let url = match m.get("u") {
+            Some(u) => u.as_str().ok_or(TxDecodeError::Malformed("notify.url"))?,
+            None => return Err(TxDecodeError::Missing("notify.url")),
+        };
+        let content_type = match m.get("ct") {
+            Some(ct) => ct
+                .as_str()
+                .ok_or(TxDecodeError::Malformed("notify.content_type"))?,
+            None => "",
+        };
+        Ok(NotifyEvent {
+            url: url.to_string(),
+            content_type: content_type.to_string(),
+            data: m.get("d").map(|d| (*d).clone()).unwrap_or(Value::Nil),
+        })
+    }
+}
+
+impl FromValue for NotifyEvent {
+    fn from_value(arg: &Value) -> Option<Self> {
+        Self::try_from(arg).ok()
+    }
+}
+
+/// Transaction signature.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Signature {
+    /// Public key of the signer, known when signatures come as a map keyed by public key.
+    pub pubkey: Option<Vec<u8>>,
+    pub sig: Vec<u8>,
+}
+
 #[derive(Debug)]
 pub struct Tx {
     pub kind: TxKind,
     pub from: Address,
     pub to: Option<Address>,
+    pub seq: u64,
     pub payload: Vec<PayloadItem>,
     pub timestamp: u64,
-    //pub not_before: u64,
+    pub not_before: Option<u64>,
+    pub call: Option<Call>,
+    pub notify: Vec<NotifyEvent>,
     pub extradata: BTreeMap<String, Value>,
+    pub signatures: Vec<Signature>,
+    /// Entries the transaction was decoded from, see `From<&Tx> for Value`.
+    pub encoding: TxEncoding,
+}
+
+/// Original map entries of decoded transaction.
+///
+/// Encoding writes fields which weren't changed since decoding exactly as they came, and
+/// keeps entries unknown to `Tx`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TxEncoding {
+    entries: Vec<(Value, Value)>,
+}
+
+/// Error returned by `Tx::require_payment`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PaymentError {
+    Insufficient {
+        currency: Currency,
+        required: u64,
+        paid: u64,
+    },
+}
+
+impl Display for PaymentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PaymentError::Insufficient {
+                currency,
+                required,
+                paid,
+            } => write!(
+                f,
+                "payment of {} {} required, {} paid",
+                required,
+                currency,
+                paid
+            ),
+        }
+    }
+}
+
+impl Tx {
+    /// Returns payload items with specified purpose.
+    pub fn payload_for(&self, purpose: Purpose) -> impl Iterator<Item = &PayloadItem> {
+        self.payload.iter().filter(move |p| p.purpose == purpose)
+    }
+
+    /// Returns payload items grouped by purpose.
+    pub fn payload_by_purpose(&self) -> BTreeMap<Purpose, Vec<&PayloadItem>> {
+        let mut res: BTreeMap<Purpose, Vec<&PayloadItem>> = BTreeMap::new();
+        for p in &self.payload {
+            res.entry(p.purpose).or_insert(Vec::new()).push(p);
+        }
+        res
+    }
+
+    /// Returns total amounts per currency of payload items with specified purpose.
+    pub fn fees(&self, purpose: Purpose) -> Balances {
+        let mut res = Balances::new();
+        for p in self.payload_for(purpose) {
+            res.saturating_add(&p.amount.currency, p.amount.amount);
+        }
+        res
+    }
+
+    /// Returns total amount of the currency transferred by this transaction.
+    pub fn transferred<C: AsRef<[u8]>>(&self, currency: C) -> u64 {
+        self.fees(Purpose::Transfer).get(currency)
+    }
+
+    /// Returns total amounts per currency paid for gas.
+    pub fn gas_paid(&self) -> Balances {
+        self.fees(Purpose::Gas)
+    }
+
+    /// Checks that at least `min` of the currency was transferred and returns transferred
+    /// amount.
+    pub fn require_payment(&self, currency: &Currency, min: u64) -> Result<u64, PaymentError> {
+        let paid = self.transferred(currency);
+        if paid < min {
+            return Err(PaymentError::Insufficient {
+                currency: currency.clone(),
+                required: min,
+                paid: paid,
+            });
+        }
+        Ok(paid)
+    }
+}
+
+/// Error returned when a transaction can't be decoded, see `Tx::try_from`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TxDecodeError {
+    /// Transaction is not a msgpack map.
+    NotAMap,
+    /// Required field is absent.
+    Missing(&'static str),
+    /// Field is present but has unexpected type or value.
+    Malformed(&'static str),
+}
+
+impl Display for TxDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TxDecodeError::NotAMap => f.write_str("transaction is not a map"),
+            TxDecodeError::Missing(field) => write!(f, "transaction field {} is missing", field),
+            TxDecodeError::Malformed(field) => {
+                write!(f, "transaction field {} is malformed", field)
+            }
+        }
+    }
+}
+
+/// Returns map entries with string keys, ignoring all other entries.
+fn map_fields(arg: &Value) -> Option<BTreeMap<&str, &Value>> {
+    let m = arg.as_map()?;
+    Some(m.iter().filter_map(|(k, v)| k.as_str().map(|k| (k, v))).collect())
+}
+
+/// Decodes address as 8 bytes binary or array of 8 integers, like `Address::from_value`.
+fn address_field(arg: &Value, field: &'static str) -> Result<Address, TxDecodeError> {
+    Address::from_value(arg).ok_or(TxDecodeError::Malformed(field))
+}
+
+fn u64_field(arg: Option<&&Value>, field: &'static str) -> Result<Option<u64>, TxDecodeError> {
+    match arg {
+        Some(v) if v.is_u64() => Ok(v.as_u64()),
+        Some(_) => Err(TxDecodeError::Malformed(field)),
+        None => Ok(None),
+    }
+}
+
+fn list_field<T>(arg: Option<&&Value>, field: &'static str) -> Result<Vec<T>, TxDecodeError>
+where
+    T: for<'a> TryFrom<&'a Value, Error = TxDecodeError>,
+{
+    match arg {
+        Some(Value::Array(a)) => a.iter().map(T::try_from).collect(),
+        Some(_) => Err(TxDecodeError::Malformed(field)),
+        None => Ok(vec![]),
+    }
+}
+
+impl TryFrom<&Value> for Tx {
+    type Error = TxDecodeError;
+
+    fn try_from(arg: &Value) -> Result<Self, TxDecodeError> {
+        let m = map_fields(arg).ok_or(TxDecodeError::NotAMap)?;
+
+        let kind = match m.get("k") {
+            Some(k) => TxKind::try_from(*k)?,
+            None => return Err(TxDecodeError::Missing("kind")),
+        };
+        let from = match m.get("f") {
+            Some(f) => address_field(f, "from")?,
+            None => return Err(TxDecodeError::Missing("from")),
+        };
+        let to = match m.get("to") {
+            Some(to) => Some(address_field(to, "to")?),
+            None => None,
+        };
+        let seq = u64_field(m.get("s"), "seq")?.unwrap_or(0);
+        let payload: Vec<PayloadItem> = list_field(m.get("p"), "payload")?;
+        let timestamp = u64_field(m.get("t"), "timestamp")?.unwrap_or(0);
+        let not_before = u64_field(m.get("nb"), "not_before")?;
+        let call = match m.get("c") {
+            Some(c) => Some(Call::try_from(*c)?),
+            None => None,
+        };
+        let notify: Vec<NotifyEvent> = list_field(m.get("ev"), "notify")?;
+
+        let mut extradata: BTreeMap<String, Value> = BTreeMap::new();
+        if let Some(e) = m.get("e") {
+            let e = map_fields(e).ok_or(TxDecodeError::Malformed("extradata"))?;
+            for (k, v) in e {
+                extradata.insert(k.to_string(), v.clone());
+            }
+        }
+
+        // Signatures come either as a list or as a map keyed by public key.
+        let sigs: Vec<(Option<&Value>, &Value)> = match m.get("sig") {
+            Some(Value::Array(a)) => a.iter().map(|s| (None, s)).collect(),
+            Some(Value::Map(sm)) => sm.iter().map(|(k, s)| (Some(k), s)).collect(),
+            Some(_) => return Err(TxDecodeError::Malformed("signatures")),
+            None => vec![],
+        };
+        let mut signatures: Vec<Signature> = Vec::with_capacity(sigs.len());
+        for (pubkey, sig) in sigs {
+            let pubkey = match pubkey {
+                Some(Value::Binary(k)) => Some(k.clone()),
+                Some(_) => return Err(TxDecodeError::Malformed("signatures")),
+                None => None,
+            };
+            match sig {
+                Value::Binary(b) => signatures.push(Signature {
+                    pubkey: pubkey,
+                    sig: b.clone(),
+                }),
+                _ => return Err(TxDecodeError::Malformed("signatures")),
+            }
+        }
+
+        Ok(Tx {
+            kind: kind,
+            from: from,
+            to: to,
+            seq: seq,
+            payload: payload,
+            timestamp: timestamp,
+            not_before: not_before,
+            call: call,
+            notify: notify,
+            extradata: extradata,
+            signatures: signatures,
+            encoding: TxEncoding {
+                entries: arg.as_map().map(|m| m.to_vec()).unwrap_or_default(),
+            },
+        })
+    }
 }
 
 impl FromValue for Tx {
     fn from_value(arg: &Value) -> Option<Self> {
-        let om: Option<BTreeMap<String, Value>> = from_value(arg);
-        if om.is_some() {
-            let m = om.unwrap();
-            let kind: TxKind = from_value(m.get("k").unwrap()).unwrap();
-            let from: Address = from_value(m.get("f").unwrap()).unwrap();
-
-            let to: Option<Address> = if m.get("to").is_some() {
-                from_value(m.get("to").unwrap())
-            } else {
-                None
-            };
+        Self::try_from(arg).ok()
+    }
+}
 
-            let payload: Vec<PayloadItem> = if m.get("p").is_some() {
-                from_value(m.get("p").unwrap()).unwrap()
-            } else {
-                vec![]
-            };
+/// Keys of fields decoded into `Tx`, in the order they are written for new fields.
+const TX_KEYS: [&str; 11] = ["c", "e", "ev", "f", "k", "nb", "p", "s", "sig", "t", "to"];
 
-            let timestamp: u64 = from_value(m.get("t").unwrap()).unwrap_or(0);
-            //let not_before: u64 = from_value(m.get("nb").unwrap_or(&Value::Integer(From::from(0)))).unwrap_or(0);
-            let extradata: BTreeMap<String, Value> = if m.get("e").is_some() {
-                from_value(m.get("e").unwrap()).unwrap()
-            } else {
-                BTreeMap::new()
-            };
-            Some(Tx {
-                kind: kind,
-                from: from,
-                to: to,
-                payload: payload,
-                timestamp: timestamp,
-                //not_before: not_before,
-                extradata: extradata,
-            })
-        } else {
-            None
+impl Tx {
+    /// Returns field encoded in node's format, or `None` if the field is omitted.
+    fn encode_field(&self, key: &str) -> Option<Value> {
+        match key {
+            "c" => self.call.as_ref().map(Value::from),
+            "e" if self.extradata.len() > 0 => Some(Value::Map(
+                self.extradata
+                    .iter()
+                    .map(|(k, v)| (k.as_str().into(), v.clone()))
+                    .collect(),
+            )),
+            "ev" if self.notify.len() > 0 => {
+                Some(Value::Array(self.notify.iter().map(Value::from).collect()))
+            }
+            "f" => Some((&self.from).into()),
+            "k" => Some(self.kind.into()),
+            "nb" => self.not_before.map(Value::from),
+            "p" => Some(Value::Array(self.payload.iter().map(Value::from).collect())),
+            "s" if self.seq > 0 => Some(self.seq.into()),
+            "sig" if self.signatures.len() > 0 => {
+                // Map form keyed by public key is used if every signature has one.
+                let keyed: Option<Vec<(Value, Value)>> = self
+                    .signatures
+                    .iter()
+                    .map(|s| {
+                        let k = s.pubkey.as_ref()?;
+                        Some((Value::Binary(k.clone()), Value::Binary(s.sig.clone())))
+                    })
+                    .collect();
+                Some(match keyed {
+                    Some(keyed) => Value::Map(keyed),
+                    None => Value::Array(
+                        self.signatures
+                            .iter()
+                            .map(|s| Value::Binary(s.sig.clone()))
+                            .collect(),
+                    ),
+                })
+            }
+            "t" if self.timestamp > 0 => Some(self.timestamp.into()),
+            "to" => self.to.as_ref().map(Value::from),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the field is the same in both transactions.
+    fn same_field(&self, other: &Tx, key: &str) -> bool {
+        match key {
+            "c" => self.call == other.call,
+            "e" => self.extradata == other.extradata,
+            "ev" => self.notify == other.notify,
+            "f" => self.from == other.from,
+            "k" => self.kind == other.kind,
+            "nb" => self.not_before == other.not_before,
+            "p" => self.payload == other.payload,
+            "s" => self.seq == other.seq,
+            "sig" => self.signatures == other.signatures,
+            "t" => self.timestamp == other.timestamp,
+            "to" => self.to == other.to,
+            _ => false,
+        }
+    }
+}
+
+impl From<&Tx> for Value {
+    /// Encodes transaction in node's format.
+    ///
+    /// Entries of decoded transaction are written back in their order and encoding,
+    /// including unknown ones, so decoding and encoding gives the same bytes. Fields
+    /// changed since decoding are written in their place in the canonical form: currencies
+    /// as binary, notify event keys sorted, signatures as a map only if all of them have
+    /// public keys. Fields absent from decoded map are appended with sorted keys if they
+    /// were set. For transaction not decoded from a map `s` and `t` are omitted when zero
+    /// and other optional fields when empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use power::*;
+    /// # use std::convert::TryFrom;
+    /// let from = Value::Binary(vec![0x80, 0x01, 0x40, 0, 0x01, 0, 0, 0x05]);
+    /// let to = Value::Binary(vec![0x80, 0x01, 0x40, 0, 0x01, 0, 0, 0x06]);
+    /// let ev = Value::Map(vec![
+    ///     ("u".into(), "https://example.com".into()),
+    ///     ("d".into(), "{}".into()),
+    /// ]);
+    /// let extra = Value::Map(vec![
+    ///     ("memo".into(), "hi".into()),
+    ///     (Value::Binary(vec![1]), 1u64.into()),
+    /// ]);
+    /// let node_tx = Value::Map(vec![
+    ///     ("k".into(), 16u64.into()),
+    ///     ("f".into(), from),
+    ///     ("to".into(), to),
+    ///     ("s".into(), 0u64.into()),
+    ///     ("c".into(), Value::Array(vec!["buy".into(), Value::Nil])),
+    ///     ("e".into(), extra),
+    ///     ("ev".into(), Value::Array(vec![ev])),
+    ///     ("sig".into(), Value::Array(vec![Value::Binary(vec![9; 64])])),
+    ///     ("x".into(), "unknown".into()),
+    /// ]);
+    /// let bytes = serialize(&node_tx);
+    ///
+    /// let mut tx = Tx::try_from(&deserialize::<Value>(&bytes).unwrap()).unwrap();
+    /// assert_eq!(bytes, serialize(&tx));
+    ///
+    /// // Changed field is written in place, new one is appended.
+    /// tx.seq = 8;
+    /// tx.timestamp = 1700000000000;
+    /// let v = Value::from(&tx);
+    /// assert_eq!(Some(8), v.as_map().unwrap()[3].1.as_u64());
+    /// assert_eq!(Some("t"), v.as_map().unwrap()[9].0.as_str());
+    /// ```
+    fn from(arg: &Tx) -> Self {
+        let entries = &arg.encoding.entries;
+        let original = Tx::try_from(&Value::Map(entries.clone())).ok();
+        let unchanged = |key: &str| match original {
+            Some(ref o) => arg.same_field(o, key),
+            None => false,
+        };
+        let mut m: Vec<(Value, Value)> = Vec::with_capacity(entries.len() + TX_KEYS.len());
+        for (k, v) in entries {
+            match k.as_str().filter(|k| TX_KEYS.contains(k)) {
+                Some(key) if !unchanged(key) => {
+                    if let Some(v) = arg.encode_field(key) {
+                        m.push((k.clone(), v));
+                    }
+                }
+                _ => m.push((k.clone(), v.clone())),
+            }
         }
+        for key in TX_KEYS.iter() {
+            if entries.iter().any(|(k, _)| k.as_str() == Some(key)) || unchanged(key) {
+                continue;
+            }
+            if let Some(v) = arg.encode_field(key) {
+                m.push(((*key).into(), v));
+            }
+        }
+        Value::Map(m)
+    }
+}
+
+impl From<Tx> for Value {
+    fn from(arg: Tx) -> Self {
+        Value::from(&arg)
     }
 }
@@ -48,7 +48,7 @@ impl Integer {
     pub fn as_u64(&self) -> Option<u64> {
         match self.n {
             IntPriv::PosInt(n) => Some(n),
-            IntPriv::NegInt(n) => Some(n as u64),
+            IntPriv::NegInt(..) => None,
         }
     }
 }
@@ -382,7 +382,7 @@ pub enum Value {
     /// # Examples
     ///
     /// ```
-    /// use Value;
+    /// use power::Value;
     ///
     /// assert_eq!(42, Value::from(42).as_i64().unwrap());
     /// ```
@@ -413,7 +413,7 @@ impl Value {
     /// # Examples
     ///
     /// ```
-    /// use Value;
+    /// use power::Value;
     ///
     /// assert!(Value::Nil.is_nil());
     /// ```
@@ -430,7 +430,7 @@ impl Value {
     /// # Examples
     ///
     /// ```
-    /// use Value;
+    /// use power::Value;
     ///
     /// assert!(Value::Boolean(true).is_bool());
     ///
@@ -445,11 +445,11 @@ impl Value {
     /// # Examples
     ///
     /// ```
-    /// use Value;
+    /// use power::Value;
     ///
     /// assert!(Value::from(42).is_i64());
     ///
-    /// assert!(!Value::from(42.0).is_i64());
+    /// assert!(!Value::Boolean(true).is_i64());
     /// ```
     pub fn is_i64(&self) -> bool {
         if let Value::Integer(ref v) = *self {
@@ -464,7 +464,7 @@ impl Value {
     /// # Examples
     ///
     /// ```
-    /// use Value;
+    /// use power::Value;
     ///
     /// assert!(Value::from(42).is_u64());
     ///
@@ -482,7 +482,7 @@ impl Value {
     /// # Examples
     ///
     /// ```
-    /// use Value;
+    /// use power::Value;
     ///
     /// assert!(Value::from(42).is_number());
     ///
@@ -500,7 +500,7 @@ impl Value {
     /// # Examples
     ///
     /// ```
-    /// use Value;
+    /// use power::Value;
     ///
     /// assert!(Value::String("value".into()).is_str());
     ///
@@ -536,7 +536,7 @@ impl Value {
     /// # Examples
     ///
     /// ```
-    /// use Value;
+    /// use power::Value;
     ///
     /// assert_eq!(Some(true), Value::Boolean(true).as_bool());
     ///
@@ -556,11 +556,11 @@ impl Value {
     /// # Examples
     ///
     /// ```
-    /// use Value;
+    /// use power::Value;
     ///
     /// assert_eq!(Some(42i64), Value::from(42).as_i64());
     ///
-    /// assert_eq!(None, Value::F64(42.0).as_i64());
+    /// assert_eq!(None, Value::Boolean(true).as_i64());
     /// ```
     pub fn as_i64(&self) -> Option<i64> {
         match *self {
@@ -575,12 +575,12 @@ impl Value {
     /// # Examples
     ///
     /// ```
-    /// use Value;
+    /// use power::Value;
     ///
     /// assert_eq!(Some(42u64), Value::from(42).as_u64());
     ///
     /// assert_eq!(None, Value::from(-42).as_u64());
-    /// assert_eq!(None, Value::F64(42.0).as_u64());
+    /// assert_eq!(None, Value::Boolean(true).as_u64());
     /// ```
     pub fn as_u64(&self) -> Option<u64> {
         match *self {
@@ -595,7 +595,7 @@ impl Value {
     /// # Examples
     ///
     /// ```
-    /// use Value;
+    /// use power::Value;
     ///
     /// assert_eq!(Some("le message"), Value::String("le message".into()).as_str());
     ///
@@ -615,7 +615,7 @@ impl Value {
     /// # Examples
     ///
     /// ```
-    /// use Value;
+    /// use power::Value;
     ///
     /// assert_eq!(Some(&[1, 2, 3, 4, 5][..]), Value::Binary(vec![1, 2, 3, 4, 5]).as_slice());
     ///
@@ -637,7 +637,7 @@ impl Value {
     /// # Examples
     ///
     /// ```
-    /// use Value;
+    /// use power::Value;
     ///
     /// let val = Value::Array(vec![Value::Nil, Value::Boolean(true)]);
     ///
@@ -663,7 +663,7 @@ impl Value {
     /// # Examples
     ///
     /// ```
-    /// use Value;
+    /// use power::Value;
     ///
     /// let val = Value::Map(vec![(Value::Nil, Value::Boolean(true))]);
     ///
@@ -685,7 +685,7 @@ impl Value {
     /// # Examples
     ///
     /// ```
-    /// use Value;
+    /// use power::Value;
     ///
     /// assert_eq!(Some((42, &[1, 2, 3, 4, 5][..])), Value::Ext(42, vec![1, 2, 3, 4, 5]).as_ext());
     ///
@@ -907,7 +907,7 @@ pub fn from_value<T: FromValue>(arg: &Value) -> Option<T> {
 impl FromValue for u64 {
     fn from_value(arg: &Value) -> Option<Self> {
         if let Value::Integer(i) = arg {
-            return Some(i.as_u64().unwrap());
+            return i.as_u64();
         }
         None
     }